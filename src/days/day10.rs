@@ -0,0 +1,165 @@
+use crate::utils::brackets::{BracketMatcher, Syntax};
+use crate::utils::challenge::Challenge;
+
+pub struct Day10 {
+    data: Vec<String>,
+}
+
+/// Scores the first illegal closing bracket. Only the four default brackets
+/// have a score, any other closing bracket scores 0.
+fn error_score(c: char) -> usize {
+    match c {
+        ')' => 3,
+        ']' => 57,
+        '}' => 1197,
+        '>' => 25137,
+        _ => 0,
+    }
+}
+
+/// Scores the brackets completing a line, like `error_score` only the four
+/// default brackets count.
+fn completion_score(completion: &str) -> usize {
+    completion.chars().fold(0, |score, c| {
+        score * 5
+            + match c {
+                ')' => 1,
+                ']' => 2,
+                '}' => 3,
+                '>' => 4,
+                _ => 0,
+            }
+    })
+}
+
+struct NavigationSubsystem {
+    lines: Vec<Syntax>,
+}
+
+impl NavigationSubsystem {
+    fn parse(lines: &[String], matcher: &BracketMatcher) -> Result<Self, String> {
+        let lines = lines
+            .iter()
+            .enumerate()
+            .map(|(i, l)| {
+                matcher
+                    .classify(l)
+                    .map_err(|e| format!("Line {}: {}", i + 1, e))
+            })
+            .collect::<Result<Vec<Syntax>, String>>()?;
+
+        Ok(Self { lines })
+    }
+
+    fn syntax_error_score(&self) -> usize {
+        self.lines
+            .iter()
+            .map(|l| match l {
+                Syntax::Corrupted { found, .. } => error_score(*found),
+                _ => 0,
+            })
+            .sum()
+    }
+
+    fn autocomplete_score(&self) -> Result<usize, String> {
+        let mut scores: Vec<usize> = self
+            .lines
+            .iter()
+            .filter_map(|l| match l {
+                Syntax::Incomplete(completion) => Some(completion_score(completion)),
+                _ => None,
+            })
+            .collect();
+        if scores.len().is_multiple_of(2) {
+            return Err(format!(
+                "Expected an odd number of incomplete lines, found {}",
+                scores.len()
+            ));
+        }
+
+        scores.sort_unstable();
+        Ok(scores[scores.len() / 2])
+    }
+}
+
+impl Challenge<String> for Day10 {
+    fn new(input_file: &str) -> Self {
+        Self {
+            data: Self::load(input_file).unwrap(),
+        }
+    }
+
+    fn run(&self, part: u32) -> Result<String, String> {
+        match part {
+            1 => self.run_part_one(),
+            2 => self.run_part_two(),
+            x => unimplemented!(
+                "Invalid part {} for Day {}",
+                x,
+                std::any::type_name::<Self>()
+            ),
+        }
+    }
+}
+
+impl Day10 {
+    fn run_part_one(&self) -> Result<String, String> {
+        let subsystem = NavigationSubsystem::parse(&self.data[..], &BracketMatcher::default())?;
+        Ok(format!("{:#?}", subsystem.syntax_error_score()))
+    }
+
+    fn run_part_two(&self) -> Result<String, String> {
+        let subsystem = NavigationSubsystem::parse(&self.data[..], &BracketMatcher::default())?;
+        Ok(format!("{:#?}", subsystem.autocomplete_score()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_example() -> Vec<String> {
+        [
+            "[({(<(())[]>[[{[]{<()<>>",
+            "[(()[<>])]({[<{<<[]>>(",
+            "{([(<{}[<>[]}>{[]{[(<()>",
+            "(((({<>}<{<{<>}{[]{[]{}",
+            "[[<[([]))<([[{}[[()]]]",
+            "[{[{({}]{}}([{[{{{}}([]",
+            "{<[[]]>}<{[{[{[]{()[[[]",
+            "[<(<(<(<{}))><([]([]()",
+            "<{([([[(<>()){}]>(<<{{",
+            "<{([{{}}[<[[[<>{}]]]>[]]",
+        ]
+        .iter()
+        .map(|l| l.to_string())
+        .collect()
+    }
+
+    #[test]
+    fn test_completion_score() {
+        assert_eq!(288957, completion_score("}}]])})]"));
+        assert_eq!(294, completion_score("])}>"));
+        // Brackets of custom pairs have no score.
+        assert_eq!(0, completion_score("bb"));
+        assert_eq!(0, error_score('b'));
+    }
+
+    #[test]
+    fn test_syntax_error_score() {
+        let subsystem =
+            NavigationSubsystem::parse(&get_example(), &BracketMatcher::default()).unwrap();
+        assert_eq!(26397, subsystem.syntax_error_score());
+    }
+
+    #[test]
+    fn test_autocomplete_score() {
+        let subsystem =
+            NavigationSubsystem::parse(&get_example(), &BracketMatcher::default()).unwrap();
+        assert_eq!(Ok(288957), subsystem.autocomplete_score());
+
+        let subsystem =
+            NavigationSubsystem::parse(&get_example()[..2], &BracketMatcher::default()).unwrap();
+        assert!(subsystem.autocomplete_score().is_err());
+    }
+}
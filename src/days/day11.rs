@@ -0,0 +1,180 @@
+use crate::utils::challenge::Challenge;
+use crate::utils::grid::{Adjacency, Grid};
+use crate::utils::simulation::Simulation;
+use std::fmt;
+
+/// Steps after which the octopuses are assumed never to synchronise.
+const SYNC_LIMIT: usize = 1 << 20;
+
+pub struct Day11 {
+    data: Vec<String>,
+    trace: bool,
+}
+
+/// Energy levels of the octopuses.
+#[derive(Debug, PartialEq)]
+struct Cavern {
+    grid: Grid<u8>,
+}
+
+impl Cavern {
+    fn parse(lines: &[String]) -> Result<Self, String> {
+        Ok(Self {
+            grid: Grid::parse_digits(lines)?,
+        })
+    }
+
+    fn size(&self) -> usize {
+        self.grid.width() * self.grid.height()
+    }
+}
+
+impl Simulation for Cavern {
+    /// The number of octopuses that flashed.
+    type Outcome = usize;
+
+    fn step(&mut self) -> usize {
+        let mut flashing = Vec::new();
+        for (pos, energy) in self.grid.iter_mut() {
+            *energy += 1;
+            if *energy > 9 {
+                flashing.push(pos);
+            }
+        }
+
+        // Every octopus enters the stack once, when its energy first exceeds 9.
+        let mut flashes = 0;
+        while let Some(pos) = flashing.pop() {
+            flashes += 1;
+            let neighbours: Vec<(usize, usize)> =
+                self.grid.neighbours(pos, Adjacency::Eight).collect();
+            for n in neighbours {
+                self.grid[n] += 1;
+                if self.grid[n] == 10 {
+                    flashing.push(n);
+                }
+            }
+        }
+
+        for (_, energy) in self.grid.iter_mut() {
+            if *energy > 9 {
+                *energy = 0;
+            }
+        }
+
+        flashes
+    }
+}
+
+impl fmt::Display for Cavern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rows: Vec<String> = (0..self.grid.height())
+            .map(|y| {
+                (0..self.grid.width())
+                    .map(|x| self.grid[(x, y)].to_string())
+                    .collect()
+            })
+            .collect();
+        write!(f, "{}", rows.join("\n"))
+    }
+}
+
+impl Challenge<String> for Day11 {
+    fn new(input_file: &str) -> Self {
+        Self {
+            data: Self::load(input_file).unwrap(),
+            trace: false,
+        }
+    }
+
+    fn run(&self, part: u32) -> Result<String, String> {
+        match part {
+            1 => self.run_part_one(),
+            2 => self.run_part_two(),
+            x => unimplemented!(
+                "Invalid part {} for Day {}",
+                x,
+                std::any::type_name::<Self>()
+            ),
+        }
+    }
+}
+
+impl Day11 {
+    /// Prints the energy levels after every step.
+    pub fn with_trace(mut self, trace: bool) -> Self {
+        self.trace = trace;
+        self
+    }
+
+    fn run_part_one(&self) -> Result<String, String> {
+        let mut cavern = Cavern::parse(&self.data[..])?;
+        let flashes: usize = cavern.run(100, self.trace).iter().sum();
+        Ok(format!("{:#?}", flashes))
+    }
+
+    fn run_part_two(&self) -> Result<String, String> {
+        let mut cavern = Cavern::parse(&self.data[..])?;
+        let size = cavern.size();
+        let step = cavern
+            .run_until(SYNC_LIMIT, self.trace, |flashes| *flashes == size)
+            .ok_or(format!(
+                "The octopuses do not flash together within {} steps",
+                SYNC_LIMIT
+            ))?;
+        Ok(format!("{:#?}", step))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &[&str]) -> Cavern {
+        let lines: Vec<String> = input.iter().map(|l| l.to_string()).collect();
+        Cavern::parse(&lines).unwrap()
+    }
+
+    fn get_example() -> Cavern {
+        parse(&[
+            "5483143223",
+            "2745854711",
+            "5264556173",
+            "6141336146",
+            "6357385478",
+            "4167524645",
+            "2176841721",
+            "6882881134",
+            "4846848554",
+            "5283751526",
+        ])
+    }
+
+    #[test]
+    fn test_step() {
+        let mut cavern = parse(&["11111", "19991", "19191", "19991", "11111"]);
+        assert_eq!(9, cavern.step());
+        assert_eq!("34543\n40004\n50005\n40004\n34543", cavern.to_string());
+        assert_eq!(0, cavern.step());
+        assert_eq!("45654\n51115\n61116\n51115\n45654", cavern.to_string());
+    }
+
+    #[test]
+    fn test_run() {
+        let mut cavern = get_example();
+        let flashes = cavern.run(10, false);
+        assert_eq!(204, flashes.iter().sum::<usize>());
+        let flashes = cavern.run(90, false);
+        assert_eq!(1656 - 204, flashes.iter().sum::<usize>());
+    }
+
+    #[test]
+    fn test_run_until() {
+        let mut cavern = get_example();
+        assert_eq!(Some(195), cavern.run_until(1000, false, |f| *f == 100));
+        assert_eq!("0000000000", cavern.to_string().lines().next().unwrap());
+
+        let mut cavern = get_example();
+        assert_eq!(None, cavern.run_until(100, false, |f| *f == 100));
+    }
+}
@@ -0,0 +1,125 @@
+use crate::utils::challenge::Challenge;
+use crate::utils::graph::Graph;
+
+pub struct Day12 {
+    data: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+struct CaveSystem {
+    graph: Graph,
+}
+
+impl CaveSystem {
+    fn parse(lines: &[String]) -> Result<Self, String> {
+        let mut graph = Graph::new();
+        for (i, line) in lines.iter().enumerate() {
+            let mut caves = line.split('-');
+            match (caves.next(), caves.next(), caves.next()) {
+                (Some(a), Some(b), None) if !a.is_empty() && !b.is_empty() => graph.add_edge(a, b),
+                _ => return Err(format!("Line {}: Invalid passage {:?}", i + 1, line)),
+            }
+        }
+
+        Ok(Self { graph })
+    }
+
+    fn is_small(&self, cave: usize) -> bool {
+        self.graph
+            .label(cave)
+            .chars()
+            .all(|c| c.is_ascii_lowercase())
+    }
+
+    /// Counts the paths from start to end visiting small caves once, except
+    /// for up to `revisits` of them.
+    fn count_paths(&self, revisits: usize) -> Result<u64, String> {
+        let start = self.graph.id("start").ok_or("No start cave")?;
+        let end = self.graph.id("end").ok_or("No end cave")?;
+        self.graph
+            .count_paths(start, end, revisits, |cave| self.is_small(cave))
+    }
+}
+
+impl Challenge<String> for Day12 {
+    fn new(input_file: &str) -> Self {
+        Self {
+            data: Self::load(input_file).unwrap(),
+        }
+    }
+
+    fn run(&self, part: u32) -> Result<String, String> {
+        match part {
+            1 => self.run_part_one(),
+            2 => self.run_part_two(),
+            x => unimplemented!(
+                "Invalid part {} for Day {}",
+                x,
+                std::any::type_name::<Self>()
+            ),
+        }
+    }
+}
+
+impl Day12 {
+    fn run_part_one(&self) -> Result<String, String> {
+        let caves = CaveSystem::parse(&self.data[..])?;
+        Ok(format!("{:#?}", caves.count_paths(0)?))
+    }
+
+    fn run_part_two(&self) -> Result<String, String> {
+        let caves = CaveSystem::parse(&self.data[..])?;
+        Ok(format!("{:#?}", caves.count_paths(1)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &[&str]) -> CaveSystem {
+        let lines: Vec<String> = input.iter().map(|l| l.to_string()).collect();
+        CaveSystem::parse(&lines).unwrap()
+    }
+
+    #[test]
+    fn test_small_example() {
+        let caves = parse(&["start-A", "start-b", "A-c", "A-b", "b-d", "A-end", "b-end"]);
+        assert_eq!(Ok(10), caves.count_paths(0));
+        assert_eq!(Ok(36), caves.count_paths(1));
+    }
+
+    #[test]
+    fn test_larger_example() {
+        let caves = parse(&[
+            "dc-end", "HN-start", "start-kj", "dc-start", "dc-HN", "LN-dc", "HN-end", "kj-sa",
+            "kj-HN", "kj-dc",
+        ]);
+        assert_eq!(Ok(19), caves.count_paths(0));
+        assert_eq!(Ok(103), caves.count_paths(1));
+    }
+
+    #[test]
+    fn test_even_larger_example() {
+        let caves = parse(&[
+            "fs-end", "he-DX", "fs-he", "start-DX", "pj-DX", "end-zg", "zg-sl", "zg-pj", "pj-he",
+            "RW-he", "fs-DX", "pj-RW", "zg-RW", "start-pj", "he-WI", "zg-he", "pj-fs", "start-RW",
+        ]);
+        assert_eq!(Ok(226), caves.count_paths(0));
+        assert_eq!(Ok(3509), caves.count_paths(1));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let lines = vec![String::from("start-A"), String::from("A")];
+        assert_eq!(
+            Err(String::from("Line 2: Invalid passage \"A\"")),
+            CaveSystem::parse(&lines)
+        );
+        assert_eq!(
+            Err(String::from("No end cave")),
+            parse(&["start-a"]).count_paths(0)
+        );
+        assert!(parse(&["start-A", "A-B", "B-end"]).count_paths(0).is_err());
+    }
+}
@@ -0,0 +1,320 @@
+use crate::utils::challenge::Challenge;
+use crate::utils::grid::Grid;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+pub struct Day13 {
+    data: Vec<String>,
+}
+
+/// Height of the capital letters folded into the paper.
+const GLYPH_HEIGHT: usize = 6;
+/// Width of a letter, followed by one blank column.
+const GLYPH_WIDTH: usize = 4;
+
+/// The letters seen so far, drawn row by row.
+const FONT: [(char, [&str; GLYPH_HEIGHT]); 16] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Fold {
+    /// Folds the right half onto the left along a column.
+    Left(usize),
+    /// Folds the bottom half up along a row.
+    Up(usize),
+}
+
+impl FromStr for Fold {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("Invalid fold: {}", s);
+        let (axis, line) = s
+            .strip_prefix("fold along ")
+            .and_then(|f| f.split_once('='))
+            .ok_or_else(err)?;
+        let line = line.parse::<usize>().map_err(|_| err())?;
+        match axis {
+            "x" => Ok(Self::Left(line)),
+            "y" => Ok(Self::Up(line)),
+            _ => Err(err()),
+        }
+    }
+}
+
+impl Fold {
+    /// Mirrors a dot across the fold line. Dots on the line or so far beyond
+    /// it that they would land off the paper are errors.
+    fn apply(&self, (x, y): (usize, usize)) -> Result<(usize, usize), String> {
+        let mirror = |pos: usize, line: usize| match pos {
+            _ if pos < line => Ok(pos),
+            _ if pos == line => Err(format!("Dot {},{} lies on the fold line", x, y)),
+            _ => (2 * line)
+                .checked_sub(pos)
+                .ok_or(format!("Dot {},{} lands off the paper", x, y)),
+        };
+        match *self {
+            Self::Left(line) => Ok((mirror(x, line)?, y)),
+            Self::Up(line) => Ok((x, mirror(y, line)?)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Paper {
+    dots: HashSet<(usize, usize)>,
+    folds: Vec<Fold>,
+}
+
+impl Paper {
+    /// Parses the dots, a blank line and the fold instructions.
+    fn parse(lines: &[String]) -> Result<Self, String> {
+        let mut sections = lines.split(|l| l.is_empty());
+        let dots = sections
+            .next()
+            .unwrap_or(&[])
+            .iter()
+            .map(|l| {
+                l.split_once(',')
+                    .and_then(|(x, y)| Some((x.parse().ok()?, y.parse().ok()?)))
+                    .ok_or(format!("Invalid dot: {}", l))
+            })
+            .collect::<Result<HashSet<(usize, usize)>, String>>()?;
+        let folds = sections
+            .next()
+            .unwrap_or(&[])
+            .iter()
+            .map(|l| l.parse())
+            .collect::<Result<Vec<Fold>, String>>()?;
+
+        Ok(Self { dots, folds })
+    }
+
+    fn fold(&mut self, fold: Fold) -> Result<(), String> {
+        self.dots = self
+            .dots
+            .iter()
+            .map(|dot| fold.apply(*dot))
+            .collect::<Result<HashSet<(usize, usize)>, String>>()?;
+        Ok(())
+    }
+
+    fn fold_all(&mut self) -> Result<(), String> {
+        for fold in self.folds.clone() {
+            self.fold(fold)?;
+        }
+        Ok(())
+    }
+
+    fn to_grid(&self) -> Grid<bool> {
+        let width = self.dots.iter().map(|(x, _)| x + 1).max().unwrap_or(0);
+        let height = self.dots.iter().map(|(_, y)| y + 1).max().unwrap_or(0);
+        let mut grid = Grid::filled(width, height, false);
+        for dot in &self.dots {
+            grid[*dot] = true;
+        }
+        grid
+    }
+
+    /// Reads the capital letters formed by the dots.
+    fn read(&self) -> Result<String, String> {
+        let grid = self.to_grid();
+        if grid.height() != GLYPH_HEIGHT {
+            return Err(format!(
+                "Expected letters {} dots high, found {}",
+                GLYPH_HEIGHT,
+                grid.height()
+            ));
+        }
+
+        let pixel = |x: usize, y: usize| x < grid.width() && grid[(x, y)];
+        let glyphs = (grid.width() + 1) / (GLYPH_WIDTH + 1);
+        (0..glyphs.max(1))
+            .map(|i| {
+                let left = i * (GLYPH_WIDTH + 1);
+                FONT.iter()
+                    .find(|(_, rows)| {
+                        rows.iter().enumerate().all(|(y, row)| {
+                            row.chars()
+                                .enumerate()
+                                .all(|(x, c)| (c == '#') == pixel(left + x, y))
+                        })
+                    })
+                    .map(|(letter, _)| *letter)
+                    .ok_or(format!("Unknown letter at column {}", left))
+            })
+            .collect()
+    }
+}
+
+impl Challenge<String> for Day13 {
+    fn new(input_file: &str) -> Self {
+        Self {
+            data: Self::load(input_file).unwrap(),
+        }
+    }
+
+    fn run(&self, part: u32) -> Result<String, String> {
+        match part {
+            1 => self.run_part_one(),
+            2 => self.run_part_two(),
+            x => unimplemented!(
+                "Invalid part {} for Day {}",
+                x,
+                std::any::type_name::<Self>()
+            ),
+        }
+    }
+}
+
+impl Day13 {
+    fn run_part_one(&self) -> Result<String, String> {
+        let mut paper = Paper::parse(&self.data[..])?;
+        let fold = *paper.folds.first().ok_or("No fold instructions")?;
+        paper.fold(fold)?;
+        Ok(format!("{:#?}", paper.dots.len()))
+    }
+
+    fn run_part_two(&self) -> Result<String, String> {
+        let mut paper = Paper::parse(&self.data[..])?;
+        paper.fold_all()?;
+        paper.read()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_example() -> Paper {
+        let lines: Vec<String> = [
+            "6,10",
+            "0,14",
+            "9,10",
+            "0,3",
+            "10,4",
+            "4,11",
+            "6,0",
+            "6,12",
+            "4,1",
+            "0,13",
+            "10,12",
+            "3,4",
+            "3,0",
+            "8,4",
+            "1,10",
+            "2,14",
+            "8,10",
+            "9,0",
+            "",
+            "fold along y=7",
+            "fold along x=5",
+        ]
+        .iter()
+        .map(|l| l.to_string())
+        .collect();
+        Paper::parse(&lines).unwrap()
+    }
+
+    /// Writes text in the font, as the folded paper would show it.
+    fn write(text: &str) -> Paper {
+        let mut dots = HashSet::new();
+        for (i, letter) in text.chars().enumerate() {
+            let (_, rows) = FONT.iter().find(|(l, _)| *l == letter).unwrap();
+            for (y, row) in rows.iter().enumerate() {
+                for (x, _) in row.chars().enumerate().filter(|(_, c)| *c == '#') {
+                    dots.insert((i * (GLYPH_WIDTH + 1) + x, y));
+                }
+            }
+        }
+        Paper {
+            dots,
+            folds: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let paper = get_example();
+        assert_eq!(18, paper.dots.len());
+        assert_eq!(vec![Fold::Up(7), Fold::Left(5)], paper.folds);
+        assert!("fold along z=1".parse::<Fold>().is_err());
+        assert!(Paper::parse(&[String::from("1;2")]).is_err());
+    }
+
+    #[test]
+    fn test_fold() {
+        let mut paper = get_example();
+        paper.fold(paper.folds[0]).unwrap();
+        assert_eq!(17, paper.dots.len());
+        paper.fold(paper.folds[1]).unwrap();
+        assert_eq!(16, paper.dots.len());
+
+        // The example folds into a square, which is no letter.
+        let grid = paper.to_grid();
+        assert_eq!((5, 5), (grid.width(), grid.height()));
+        assert!(paper.read().is_err());
+    }
+
+    #[test]
+    fn test_read() {
+        assert_eq!(
+            Ok(String::from("ABCEFGHJKLOPRSUZ")),
+            write("ABCEFGHJKLOPRSUZ").read()
+        );
+        assert_eq!(Ok(String::from("L")), write("L").read());
+
+        let mut paper = write("HELLO");
+        paper.dots.insert((2, 0));
+        assert_eq!(
+            Err(String::from("Unknown letter at column 0")),
+            paper.read()
+        );
+    }
+
+    #[test]
+    fn test_fold_all() {
+        // Mirror "EZ" below itself and fold it back up.
+        let mut paper = write("EZ");
+        let mirrored: Vec<(usize, usize)> = paper.dots.iter().map(|(x, y)| (*x, 12 - y)).collect();
+        paper.dots.extend(mirrored);
+        paper.folds = vec![Fold::Up(6)];
+        paper.fold_all().unwrap();
+        assert_eq!(Ok(String::from("EZ")), paper.read());
+    }
+
+    #[test]
+    fn test_fold_errors() {
+        let mut paper = Paper::parse(&[String::from("10,0")]).unwrap();
+        assert_eq!(
+            Err(String::from("Dot 10,0 lands off the paper")),
+            paper.fold(Fold::Left(2))
+        );
+
+        let mut paper = Paper::parse(&[String::from("1,3")]).unwrap();
+        assert_eq!(
+            Err(String::from("Dot 1,3 lies on the fold line")),
+            paper.fold(Fold::Up(3))
+        );
+        assert_eq!(
+            Err(String::from("Dot 1,3 lies on the fold line")),
+            paper.fold(Fold::Left(1))
+        );
+    }
+}
@@ -0,0 +1,214 @@
+use crate::utils::challenge::Challenge;
+use std::collections::HashMap;
+
+pub struct Day14 {
+    data: Vec<String>,
+}
+
+type Pair = (char, char);
+
+#[derive(Debug, PartialEq)]
+struct Polymerization {
+    template: String,
+    rules: HashMap<Pair, char>,
+}
+
+impl Polymerization {
+    /// Parses the template, a blank line and rules like `CH -> B`.
+    fn parse(lines: &[String]) -> Result<Self, String> {
+        let template = lines.first().ok_or("No polymer template")?.clone();
+        if template.is_empty() {
+            return Err(String::from("Empty polymer template"));
+        }
+        if let Some(line) = lines.get(1).filter(|l| !l.is_empty()) {
+            return Err(format!(
+                "Expected a blank line after the template, found: {}",
+                line
+            ));
+        }
+
+        let rules = lines
+            .iter()
+            .skip(2)
+            .map(|l| {
+                let rule: Vec<char> = l.chars().collect();
+                match rule[..] {
+                    [a, b, ' ', '-', '>', ' ', c] => Ok(((a, b), c)),
+                    _ => Err(format!("Invalid insertion rule: {}", l)),
+                }
+            })
+            .collect::<Result<HashMap<Pair, char>, String>>()?;
+
+        Ok(Self { template, rules })
+    }
+
+    fn pairs(&self) -> HashMap<Pair, u64> {
+        let mut pairs = HashMap::new();
+        let chars: Vec<char> = self.template.chars().collect();
+        for pair in chars.windows(2) {
+            *pairs.entry((pair[0], pair[1])).or_insert(0) += 1;
+        }
+        pairs
+    }
+
+    /// Inserts an element into every pair with a rule. Pairs without a rule
+    /// stay as they are.
+    fn step(&self, pairs: &HashMap<Pair, u64>) -> Option<HashMap<Pair, u64>> {
+        let mut next = HashMap::new();
+        for (&(a, b), &count) in pairs {
+            let produced = match self.rules.get(&(a, b)) {
+                Some(&c) => vec![(a, c), (c, b)],
+                None => vec![(a, b)],
+            };
+            for pair in produced {
+                let entry = next.entry(pair).or_insert(0u64);
+                *entry = entry.checked_add(count)?;
+            }
+        }
+        Some(next)
+    }
+
+    /// Counts the elements after a number of steps. Every element but the
+    /// first is the second of a pair.
+    fn count_elements(&self, steps: usize) -> Result<HashMap<char, u64>, String> {
+        let mut pairs = self.pairs();
+        for step in 0..steps {
+            pairs = self
+                .step(&pairs)
+                .ok_or(format!("Polymer overflows u64 after {} steps", step + 1))?;
+        }
+
+        let mut elements = HashMap::new();
+        elements.insert(self.template.chars().next().unwrap(), 1);
+        for (&(_, b), &count) in &pairs {
+            let entry = elements.entry(b).or_insert(0u64);
+            *entry = entry
+                .checked_add(count)
+                .ok_or(format!("Polymer overflows u64 after {} steps", steps))?;
+        }
+        Ok(elements)
+    }
+
+    /// The quantity of the most common element minus the least common one.
+    fn spread(&self, steps: usize) -> Result<u64, String> {
+        let elements = self.count_elements(steps)?;
+        let most = elements.values().max().unwrap();
+        let least = elements.values().min().unwrap();
+        Ok(most - least)
+    }
+}
+
+impl Challenge<String> for Day14 {
+    fn new(input_file: &str) -> Self {
+        Self {
+            data: Self::load(input_file).unwrap(),
+        }
+    }
+
+    fn run(&self, part: u32) -> Result<String, String> {
+        match part {
+            1 => self.run_part_one(),
+            2 => self.run_part_two(),
+            x => unimplemented!(
+                "Invalid part {} for Day {}",
+                x,
+                std::any::type_name::<Self>()
+            ),
+        }
+    }
+}
+
+impl Day14 {
+    fn run_part_one(&self) -> Result<String, String> {
+        let polymer = Polymerization::parse(&self.data[..])?;
+        Ok(format!("{:#?}", polymer.spread(10)?))
+    }
+
+    fn run_part_two(&self) -> Result<String, String> {
+        let polymer = Polymerization::parse(&self.data[..])?;
+        Ok(format!("{:#?}", polymer.spread(40)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_example() -> Polymerization {
+        let lines: Vec<String> = [
+            "NNCB", "", "CH -> B", "HH -> N", "CB -> H", "NH -> C", "HB -> C", "HC -> B",
+            "HN -> C", "NN -> C", "BH -> H", "NC -> B", "NB -> B", "BN -> B", "BB -> N", "BC -> B",
+            "CC -> N", "CN -> C",
+        ]
+        .iter()
+        .map(|l| l.to_string())
+        .collect();
+        Polymerization::parse(&lines).unwrap()
+    }
+
+    /// Builds the polymer itself, which doubles in length with every step.
+    fn expand(polymer: &Polymerization, steps: usize) -> String {
+        let mut chain = polymer.template.clone();
+        for _ in 0..steps {
+            let chars: Vec<char> = chain.chars().collect();
+            let mut next = String::new();
+            next.push(chars[0]);
+            for pair in chars.windows(2) {
+                if let Some(c) = polymer.rules.get(&(pair[0], pair[1])) {
+                    next.push(*c);
+                }
+                next.push(pair[1]);
+            }
+            chain = next;
+        }
+        chain
+    }
+
+    #[test]
+    fn test_parse() {
+        let polymer = get_example();
+        assert_eq!("NNCB", polymer.template);
+        assert_eq!(16, polymer.rules.len());
+        assert_eq!(Some(&'B'), polymer.rules.get(&('C', 'H')));
+
+        let lines = vec![String::from("NN"), String::new(), String::from("NN => C")];
+        assert!(Polymerization::parse(&lines).is_err());
+        assert!(Polymerization::parse(&[]).is_err());
+
+        // Without the blank line the first rule would be dropped.
+        let lines = vec![String::from("NN"), String::from("NN -> C")];
+        assert_eq!(
+            Err(String::from(
+                "Expected a blank line after the template, found: NN -> C"
+            )),
+            Polymerization::parse(&lines)
+        );
+    }
+
+    #[test]
+    fn test_expand() {
+        let polymer = get_example();
+        assert_eq!("NCNBCHB", expand(&polymer, 1));
+        assert_eq!("NBBBCNCCNBBNBNBBCHBHHBCHB", expand(&polymer, 3));
+    }
+
+    #[test]
+    fn test_count_matches_expansion() {
+        let polymer = get_example();
+        for steps in 0..=10 {
+            let mut expected = HashMap::new();
+            for c in expand(&polymer, steps).chars() {
+                *expected.entry(c).or_insert(0) += 1;
+            }
+            assert_eq!(Ok(expected), polymer.count_elements(steps));
+        }
+    }
+
+    #[test]
+    fn test_spread() {
+        let polymer = get_example();
+        assert_eq!(Ok(1588), polymer.spread(10));
+        assert_eq!(Ok(2188189693529), polymer.spread(40));
+        assert!(polymer.spread(100).is_err());
+    }
+}
@@ -0,0 +1,238 @@
+use crate::utils::challenge::Challenge;
+use crate::utils::grid::{Adjacency, Grid};
+use crate::utils::pathfinding::{astar, dijkstra, Path};
+use std::collections::HashSet;
+use std::str::FromStr;
+
+pub struct Day15 {
+    data: Vec<String>,
+    search: Search,
+    show_path: bool,
+}
+
+/// The shortest path algorithm to use.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Search {
+    Dijkstra,
+    /// A* guided by the Manhattan distance, as every step costs at least 1.
+    AStar,
+}
+
+impl FromStr for Search {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dijkstra" => Ok(Self::Dijkstra),
+            "astar" => Ok(Self::AStar),
+            x => Err(format!("Unknown search: {}", x)),
+        }
+    }
+}
+
+type Position = (usize, usize);
+
+#[derive(Debug, PartialEq)]
+struct RiskMap {
+    grid: Grid<u8>,
+}
+
+impl RiskMap {
+    fn parse(lines: &[String]) -> Result<Self, String> {
+        let grid = Grid::parse_digits(lines)?;
+        if grid.width() == 0 || grid.height() == 0 {
+            return Err(String::from("Empty risk map"));
+        }
+        if grid.iter().any(|(_, risk)| *risk == 0) {
+            return Err(String::from("Risk levels must be between 1 and 9"));
+        }
+
+        Ok(Self { grid })
+    }
+
+    /// Repeats the map to the right and downwards, adding 1 to the risk for
+    /// every repetition and wrapping from 9 back to 1.
+    fn tiled(&self, factor: usize) -> Self {
+        let (width, height) = (self.grid.width(), self.grid.height());
+        let mut grid = Grid::filled(width * factor, height * factor, 0);
+        for ((x, y), risk) in grid.iter_mut() {
+            let increase = x / width + y / height;
+            *risk = ((self.grid[(x % width, y % height)] as usize - 1 + increase) % 9 + 1) as u8;
+        }
+
+        Self { grid }
+    }
+
+    fn exit(&self) -> Position {
+        (self.grid.width() - 1, self.grid.height() - 1)
+    }
+
+    /// Finds the path from the top left to the bottom right with the lowest
+    /// total risk of the entered positions.
+    fn lowest_risk(&self, search: Search) -> Result<Path<Position>, String> {
+        let exit = self.exit();
+        let successors = |pos: &Position| {
+            self.grid
+                .neighbours(*pos, Adjacency::Four)
+                .map(|n| (n, self.grid[n] as u64))
+                .collect::<Vec<_>>()
+        };
+        let is_exit = |pos: &Position| *pos == exit;
+
+        match search {
+            Search::Dijkstra => dijkstra((0, 0), successors, is_exit),
+            Search::AStar => astar(
+                (0, 0),
+                successors,
+                |(x, y)| ((exit.0 - x) + (exit.1 - y)) as u64,
+                is_exit,
+            ),
+        }
+        .ok_or_else(|| String::from("No path to the exit"))
+    }
+
+    /// Shows the risk levels along a path and dots everywhere else.
+    fn render_path(&self, path: &[Position]) -> String {
+        let on_path: HashSet<&Position> = path.iter().collect();
+        let rows: Vec<String> = (0..self.grid.height())
+            .map(|y| {
+                (0..self.grid.width())
+                    .map(|x| {
+                        if on_path.contains(&(x, y)) {
+                            (b'0' + self.grid[(x, y)]) as char
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        rows.join("\n")
+    }
+}
+
+impl Challenge<String> for Day15 {
+    fn new(input_file: &str) -> Self {
+        Self {
+            data: Self::load(input_file).unwrap(),
+            search: Search::Dijkstra,
+            show_path: false,
+        }
+    }
+
+    fn run(&self, part: u32) -> Result<String, String> {
+        match part {
+            1 => self.run_part_one(),
+            2 => self.run_part_two(),
+            x => unimplemented!(
+                "Invalid part {} for Day {}",
+                x,
+                std::any::type_name::<Self>()
+            ),
+        }
+    }
+}
+
+impl Day15 {
+    pub fn with_search(mut self, search: Search) -> Self {
+        self.search = search;
+        self
+    }
+
+    /// Prints the map with the lowest risk path.
+    pub fn with_show_path(mut self, show_path: bool) -> Self {
+        self.show_path = show_path;
+        self
+    }
+
+    fn solve(&self, map: &RiskMap) -> Result<String, String> {
+        let path = map.lowest_risk(self.search)?;
+        if self.show_path {
+            println!("{}\n", map.render_path(&path.nodes));
+        }
+
+        Ok(format!("{:#?}", path.cost))
+    }
+
+    fn run_part_one(&self) -> Result<String, String> {
+        let map = RiskMap::parse(&self.data[..])?;
+        self.solve(&map)
+    }
+
+    fn run_part_two(&self) -> Result<String, String> {
+        let map = RiskMap::parse(&self.data[..])?.tiled(5);
+        self.solve(&map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &[&str]) -> RiskMap {
+        let lines: Vec<String> = input.iter().map(|l| l.to_string()).collect();
+        RiskMap::parse(&lines).unwrap()
+    }
+
+    fn get_example() -> RiskMap {
+        parse(&[
+            "1163751742",
+            "1381373672",
+            "2136511328",
+            "3694931569",
+            "7463417111",
+            "1319128137",
+            "1359912421",
+            "3125421639",
+            "1293138521",
+            "2311944581",
+        ])
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!(RiskMap::parse(&[String::from("109")]).is_err());
+        assert!(RiskMap::parse(&[]).is_err());
+    }
+
+    #[test]
+    fn test_tiled() {
+        let map = parse(&["8"]).tiled(5);
+        assert_eq!(
+            "89123\n91234\n12345\n23456\n34567",
+            map.render_path(&map.grid.iter().map(|(p, _)| p).collect::<Vec<_>>())
+        );
+
+        let map = get_example().tiled(5);
+        assert_eq!((50, 50), (map.grid.width(), map.grid.height()));
+        assert_eq!(9, map.grid[(49, 49)]);
+        assert_eq!(2, map.grid[(10, 0)]);
+    }
+
+    #[test]
+    fn test_lowest_risk() {
+        let map = get_example();
+        let path = map.lowest_risk(Search::Dijkstra).unwrap();
+        assert_eq!(40, path.cost);
+        assert_eq!(Some(&(0, 0)), path.nodes.first());
+        assert_eq!(Some(&(9, 9)), path.nodes.last());
+        let risk: u64 = path.nodes[1..].iter().map(|p| map.grid[*p] as u64).sum();
+        assert_eq!(40, risk);
+        assert_eq!(40, map.lowest_risk(Search::AStar).unwrap().cost);
+    }
+
+    #[test]
+    fn test_lowest_risk_tiled() {
+        let map = get_example().tiled(5);
+        assert_eq!(315, map.lowest_risk(Search::Dijkstra).unwrap().cost);
+        assert_eq!(315, map.lowest_risk(Search::AStar).unwrap().cost);
+    }
+
+    #[test]
+    fn test_render_path() {
+        let map = parse(&["19", "11"]);
+        let path = map.lowest_risk(Search::Dijkstra).unwrap();
+        assert_eq!(vec![(0, 0), (0, 1), (1, 1)], path.nodes);
+        assert_eq!("1.\n11", map.render_path(&path.nodes));
+    }
+}
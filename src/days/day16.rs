@@ -0,0 +1,495 @@
+use crate::utils::challenge::Challenge;
+use std::convert::TryFrom;
+use std::fmt;
+
+pub struct Day16 {
+    data: Vec<String>,
+    ast: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Sum,
+    Product,
+    Minimum,
+    Maximum,
+    GreaterThan,
+    LessThan,
+    EqualTo,
+}
+
+impl TryFrom<u64> for Operator {
+    type Error = String;
+
+    fn try_from(type_id: u64) -> Result<Self, Self::Error> {
+        match type_id {
+            0 => Ok(Self::Sum),
+            1 => Ok(Self::Product),
+            2 => Ok(Self::Minimum),
+            3 => Ok(Self::Maximum),
+            5 => Ok(Self::GreaterThan),
+            6 => Ok(Self::LessThan),
+            7 => Ok(Self::EqualTo),
+            x => Err(format!("Unknown operator type {}", x)),
+        }
+    }
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Sum => "sum",
+            Self::Product => "product",
+            Self::Minimum => "minimum",
+            Self::Maximum => "maximum",
+            Self::GreaterThan => "greater than",
+            Self::LessThan => "less than",
+            Self::EqualTo => "equal to",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How an operator packet announces the extent of its sub-packets.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Length {
+    Bits(usize),
+    Packets(usize),
+}
+
+#[derive(Debug, PartialEq)]
+enum Payload {
+    Literal(u64),
+    Operator {
+        operator: Operator,
+        length: Length,
+        packets: Vec<Packet>,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+struct Packet {
+    version: u8,
+    payload: Payload,
+}
+
+/// Deepest nesting of operator packets accepted, so malformed input can't
+/// exhaust the stack.
+const MAX_DEPTH: usize = 256;
+
+/// Reads a transmission bit by bit, most significant bit first.
+struct BitReader {
+    bits: Vec<bool>,
+    pos: usize,
+}
+
+impl BitReader {
+    fn from_hex(hex: &str) -> Result<Self, String> {
+        let mut bits = Vec::with_capacity(4 * hex.len());
+        for (i, c) in hex.trim_end().chars().enumerate() {
+            let nibble = c
+                .to_digit(16)
+                .ok_or(format!("Invalid hex digit {:?} at {}", c, i))?;
+            bits.extend((0..4).rev().map(|b| nibble & 1 << b != 0));
+        }
+
+        Ok(Self { bits, pos: 0 })
+    }
+
+    fn read(&mut self, count: usize) -> Result<u64, String> {
+        if self.pos + count > self.bits.len() {
+            return Err(format!(
+                "Truncated transmission: needed {} bits at bit {}, {} left",
+                count,
+                self.pos,
+                self.bits.len() - self.pos
+            ));
+        }
+
+        let value = self.bits[self.pos..self.pos + count]
+            .iter()
+            .fold(0, |value, bit| value << 1 | *bit as u64);
+        self.pos += count;
+        Ok(value)
+    }
+
+    fn read_literal(&mut self) -> Result<u64, String> {
+        let start = self.pos;
+        let mut value: u64 = 0;
+        loop {
+            let group = self.read(5)?;
+            if value.leading_zeros() < 4 {
+                return Err(format!("Literal at bit {} exceeds 64 bits", start));
+            }
+            value = value << 4 | group & 0xf;
+            if group & 0x10 == 0 {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Reads a packet nested `depth` levels below the outermost one.
+    fn read_packet(&mut self, depth: usize) -> Result<Packet, String> {
+        if depth > MAX_DEPTH {
+            return Err(format!(
+                "Packet at bit {} is nested deeper than {} levels",
+                self.pos, MAX_DEPTH
+            ));
+        }
+
+        let version = self.read(3)? as u8;
+        let type_id = self.read(3)?;
+        if type_id == 4 {
+            let value = self.read_literal()?;
+            return Ok(Packet {
+                version,
+                payload: Payload::Literal(value),
+            });
+        }
+
+        let start = self.pos - 6;
+        let operator =
+            Operator::try_from(type_id).map_err(|e| format!("{} at bit {}", e, start))?;
+        let mut packets = Vec::new();
+        let length = if self.read(1)? == 0 {
+            let bits = self.read(15)? as usize;
+            let end = self.pos + bits;
+            while self.pos < end {
+                packets.push(self.read_packet(depth + 1)?);
+            }
+            if self.pos > end {
+                return Err(format!(
+                    "Sub-packets of the packet at bit {} overrun its {} bits",
+                    start, bits
+                ));
+            }
+            Length::Bits(bits)
+        } else {
+            let count = self.read(11)? as usize;
+            for _ in 0..count {
+                packets.push(self.read_packet(depth + 1)?);
+            }
+            Length::Packets(count)
+        };
+
+        match (operator, packets.len()) {
+            (_, 0) => Err(format!(
+                "Operator packet at bit {} has no sub-packets",
+                start
+            )),
+            (Operator::GreaterThan, n) | (Operator::LessThan, n) | (Operator::EqualTo, n)
+                if n != 2 =>
+            {
+                Err(format!(
+                    "Comparison at bit {} needs 2 sub-packets, found {}",
+                    start, n
+                ))
+            }
+            _ => Ok(Packet {
+                version,
+                payload: Payload::Operator {
+                    operator,
+                    length,
+                    packets,
+                },
+            }),
+        }
+    }
+}
+
+impl Packet {
+    /// Decodes the outermost packet of a hex transmission, which may only be
+    /// followed by zero padding.
+    fn decode(hex: &str) -> Result<Self, String> {
+        let mut reader = BitReader::from_hex(hex)?;
+        let packet = reader.read_packet(0)?;
+        if let Some(pos) = reader.bits[reader.pos..].iter().position(|bit| *bit) {
+            return Err(format!(
+                "Unexpected data after the packet at bit {}",
+                reader.pos + pos
+            ));
+        }
+
+        Ok(packet)
+    }
+
+    fn version_sum(&self) -> u64 {
+        let nested = match &self.payload {
+            Payload::Literal(_) => 0,
+            Payload::Operator { packets, .. } => packets.iter().map(|p| p.version_sum()).sum(),
+        };
+        self.version as u64 + nested
+    }
+
+    fn evaluate(&self) -> Result<u64, String> {
+        let (operator, packets) = match &self.payload {
+            Payload::Literal(value) => return Ok(*value),
+            Payload::Operator {
+                operator, packets, ..
+            } => (operator, packets),
+        };
+
+        let values = packets
+            .iter()
+            .map(|p| p.evaluate())
+            .collect::<Result<Vec<u64>, String>>()?;
+        let overflow = || format!("Overflow evaluating the {}", operator);
+        match operator {
+            Operator::Sum => values
+                .iter()
+                .try_fold(0u64, |acc, v| acc.checked_add(*v))
+                .ok_or_else(overflow),
+            Operator::Product => values
+                .iter()
+                .try_fold(1u64, |acc, v| acc.checked_mul(*v))
+                .ok_or_else(overflow),
+            Operator::Minimum => Ok(*values.iter().min().unwrap()),
+            Operator::Maximum => Ok(*values.iter().max().unwrap()),
+            Operator::GreaterThan => Ok((values[0] > values[1]) as u64),
+            Operator::LessThan => Ok((values[0] < values[1]) as u64),
+            Operator::EqualTo => Ok((values[0] == values[1]) as u64),
+        }
+    }
+
+    fn fmt_indented(&self, f: &mut fmt::Formatter, depth: usize) -> fmt::Result {
+        let indent = "  ".repeat(depth);
+        match &self.payload {
+            Payload::Literal(value) => writeln!(f, "{}{} (v{})", indent, value, self.version),
+            Payload::Operator {
+                operator,
+                length,
+                packets,
+            } => {
+                let length = match length {
+                    Length::Bits(bits) => format!("{} bits", bits),
+                    Length::Packets(count) => format!("{} packets", count),
+                };
+                writeln!(f, "{}{} (v{}, {})", indent, operator, self.version, length)?;
+                for packet in packets {
+                    packet.fmt_indented(f, depth + 1)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for Packet {
+    /// Prints the packet tree, one packet per line indented by its depth.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_indented(f, 0)
+    }
+}
+
+impl Challenge<String> for Day16 {
+    fn new(input_file: &str) -> Self {
+        Self {
+            data: Self::load(input_file).unwrap(),
+            ast: false,
+        }
+    }
+
+    fn run(&self, part: u32) -> Result<String, String> {
+        match part {
+            1 => self.run_part_one(),
+            2 => self.run_part_two(),
+            x => unimplemented!(
+                "Invalid part {} for Day {}",
+                x,
+                std::any::type_name::<Self>()
+            ),
+        }
+    }
+}
+
+impl Day16 {
+    /// Prints the decoded packet tree.
+    pub fn with_ast(mut self, ast: bool) -> Self {
+        self.ast = ast;
+        self
+    }
+
+    fn decode(&self) -> Result<Packet, String> {
+        let packet = Packet::decode(self.data.first().ok_or("Empty transmission")?)?;
+        if self.ast {
+            print!("{}", packet);
+        }
+        Ok(packet)
+    }
+
+    fn run_part_one(&self) -> Result<String, String> {
+        Ok(format!("{:#?}", self.decode()?.version_sum()))
+    }
+
+    fn run_part_two(&self) -> Result<String, String> {
+        Ok(format!("{:#?}", self.decode()?.evaluate()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pads a bit string with zeros to whole hex digits.
+    fn to_hex(bits: &str) -> String {
+        let mut bits = bits.to_string();
+        while !bits.len().is_multiple_of(4) {
+            bits.push('0');
+        }
+        bits.as_bytes()
+            .chunks(4)
+            .map(|nibble| {
+                let nibble = std::str::from_utf8(nibble).unwrap();
+                format!("{:X}", u8::from_str_radix(nibble, 2).unwrap())
+            })
+            .collect()
+    }
+
+    /// Nests a literal 1 in `depth` sum packets with one sub-packet each.
+    fn nested(depth: usize) -> String {
+        to_hex(&("000000100000000001".repeat(depth) + "00010000001"))
+    }
+
+    fn literal(version: u8, value: u64) -> Packet {
+        Packet {
+            version,
+            payload: Payload::Literal(value),
+        }
+    }
+
+    #[test]
+    fn test_decode_literal() {
+        assert_eq!(Ok(literal(6, 2021)), Packet::decode("D2FE28"));
+    }
+
+    #[test]
+    fn test_decode_operator() {
+        let expected = Packet {
+            version: 1,
+            payload: Payload::Operator {
+                operator: Operator::LessThan,
+                length: Length::Bits(27),
+                packets: vec![literal(6, 10), literal(2, 20)],
+            },
+        };
+        assert_eq!(Ok(expected), Packet::decode("38006F45291200"));
+
+        let expected = Packet {
+            version: 7,
+            payload: Payload::Operator {
+                operator: Operator::Maximum,
+                length: Length::Packets(3),
+                packets: vec![literal(2, 1), literal(4, 2), literal(1, 3)],
+            },
+        };
+        assert_eq!(Ok(expected), Packet::decode("EE00D40C823060"));
+    }
+
+    #[test]
+    fn test_version_sum() {
+        let examples = [
+            ("8A004A801A8002F478", 16),
+            ("620080001611562C8802118E34", 12),
+            ("C0015000016115A2E0802F182340", 23),
+            ("A0016C880162017C3686B18A3D4780", 31),
+        ];
+        for (hex, sum) in examples.iter() {
+            assert_eq!(*sum, Packet::decode(hex).unwrap().version_sum(), "{}", hex);
+        }
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let examples = [
+            ("C200B40A82", 3),
+            ("04005AC33890", 54),
+            ("880086C3E88112", 7),
+            ("CE00C43D881120", 9),
+            ("D8005AC2A8F0", 1),
+            ("F600BC2D8F", 0),
+            ("9C005AC2F8F0", 0),
+            ("9C0141080250320F1802104A08", 1),
+        ];
+        for (hex, value) in examples.iter() {
+            assert_eq!(
+                Ok(*value),
+                Packet::decode(hex).unwrap().evaluate(),
+                "{}",
+                hex
+            );
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        let packet = Packet::decode("9C0141080250320F1802104A08").unwrap();
+        let expected = "\
+equal to (v4, 80 bits)
+  sum (v2, 2 packets)
+    1 (v2)
+    3 (v4)
+  product (v6, 2 packets)
+    2 (v0)
+    2 (v2)
+";
+        assert_eq!(expected, packet.to_string());
+    }
+
+    #[test]
+    fn test_malformed() {
+        assert_eq!(
+            Err(String::from("Invalid hex digit 'G' at 2")),
+            Packet::decode("D2G")
+        );
+        assert_eq!(
+            Err(String::from(
+                "Truncated transmission: needed 5 bits at bit 16, 0 left"
+            )),
+            Packet::decode("D2FE")
+        );
+        assert_eq!(
+            Err(String::from("Unexpected data after the packet at bit 21")),
+            Packet::decode("D2FE2C")
+        );
+        assert_eq!(
+            Err(String::from(
+                "Comparison at bit 0 needs 2 sub-packets, found 1"
+            )),
+            Packet::decode("16004408")
+        );
+        assert_eq!(
+            Err(String::from(
+                "Sub-packets of the packet at bit 0 overrun its 10 bits"
+            )),
+            Packet::decode("000028408")
+        );
+    }
+
+    #[test]
+    fn test_oversized_literal() {
+        let max = format!("000100{}01111", "11111".repeat(15));
+        assert_eq!(Ok(literal(0, u64::MAX)), Packet::decode(&to_hex(&max)));
+
+        let oversized = format!("000100{}01111", "11111".repeat(16));
+        assert_eq!(
+            Err(String::from("Literal at bit 6 exceeds 64 bits")),
+            Packet::decode(&to_hex(&oversized))
+        );
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        let packet = Packet::decode(&nested(MAX_DEPTH)).unwrap();
+        assert_eq!(Ok(1), packet.evaluate());
+
+        assert_eq!(
+            Err(format!(
+                "Packet at bit {} is nested deeper than {} levels",
+                18 * (MAX_DEPTH + 1),
+                MAX_DEPTH
+            )),
+            Packet::decode(&nested(MAX_DEPTH + 1))
+        );
+        assert!(Packet::decode(&nested(100_000)).is_err());
+    }
+}
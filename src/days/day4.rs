@@ -1,14 +1,20 @@
 use crate::utils::challenge::Challenge;
 use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
 use std::thread;
 
 pub struct Day4 {
     data: Vec<String>,
+    tie_break: TieBreak,
+    replay: Option<ReplayMode>,
+    color: bool,
 }
 
 #[derive(Debug, PartialEq)]
 struct BingoBoard {
     data: HashMap<u32, (usize, usize)>,
+    grid: Vec<Vec<u32>>,
     row_cnt: Vec<u32>,
     col_cnt: Vec<u32>,
 }
@@ -27,36 +33,114 @@ impl BingoBoard {
     fn play(&mut self, input: &[u32]) -> Option<(usize, u32, u32)> {
         for (i, v) in input.iter().enumerate() {
             if self.data.contains_key(v) && self.check(v) {
-                let sum_unmarked: u32 = self.data.iter().map(|(key, _)| *key).sum();
+                let sum_unmarked: u32 = self.data.keys().sum();
                 return Some((i, *v, sum_unmarked));
             }
         }
 
         None
     }
+
+    fn is_marked(&self, num: &u32) -> bool {
+        !self.data.contains_key(num)
+    }
+
+    fn render(&self, color: bool) -> String {
+        self.grid
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|num| match (self.is_marked(num), color) {
+                        (false, _) => format!("{:>2} ", num),
+                        (true, false) => format!("{:>2}*", num),
+                        (true, true) => format!("\x1b[1;32m{:>2}\x1b[0m ", num),
+                    })
+                    .collect::<Vec<String>>()
+                    .join(" ")
+                    .trim_end()
+                    .to_string()
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
 }
 
 impl From<&[String]> for BingoBoard {
     fn from(input: &[String]) -> Self {
-        let mut data: HashMap<u32, (usize, usize)> = HashMap::new();
-        for (i, l) in input.iter().enumerate() {
-            data.extend(
+        let grid: Vec<Vec<u32>> = input
+            .iter()
+            .map(|l| {
                 l.split(' ')
                     .filter(|s| !s.is_empty())
-                    .enumerate()
-                    .map(|(j, s)| (s.parse::<u32>().unwrap(), (i, j)))
-                    .collect::<HashMap<u32, (usize, usize)>>(),
-            )
+                    .map(|s| s.parse::<u32>().unwrap())
+                    .collect()
+            })
+            .collect();
+
+        let mut data: HashMap<u32, (usize, usize)> = HashMap::new();
+        for (i, row) in grid.iter().enumerate() {
+            data.extend(row.iter().enumerate().map(|(j, num)| (*num, (i, j))));
         }
 
         BingoBoard {
             data,
+            grid,
             row_cnt: vec![0, 0, 0, 0, 0],
             col_cnt: vec![0, 0, 0, 0, 0],
         }
     }
 }
 
+/// How to pick a board when several complete on the same draw.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TieBreak {
+    /// Prefer the board that comes first in the input.
+    FirstBoard,
+    /// Prefer the board that comes last in the input.
+    LastBoard,
+}
+
+impl FromStr for TieBreak {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "first" => Ok(Self::FirstBoard),
+            "last" => Ok(Self::LastBoard),
+            x => Err(format!("Unknown tie break policy: {}", x)),
+        }
+    }
+}
+
+impl TieBreak {
+    /// Picks one of the boards that won on `draw`, given wins ordered by
+    /// draw index and then by board index.
+    fn pick(&self, wins: &[BingoWin], draw: usize) -> Option<BingoWin> {
+        let mut tied = wins.iter().filter(|w| w.draw == draw);
+        match self {
+            Self::FirstBoard => tied.next(),
+            Self::LastBoard => tied.next_back(),
+        }
+        .copied()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct BingoWin {
+    /// Index of the board in input order.
+    board: usize,
+    /// Index of the winning number in the draw sequence.
+    draw: usize,
+    last: u32,
+    sum_unmarked: u32,
+}
+
+impl BingoWin {
+    fn score(&self) -> u32 {
+        self.last * self.sum_unmarked
+    }
+}
+
 #[derive(Debug, PartialEq)]
 struct BingoSubsystem {
     input: Vec<u32>,
@@ -80,47 +164,223 @@ impl From<&[String]> for BingoSubsystem {
 }
 
 impl BingoSubsystem {
-    fn play(mut self) -> (u32, u32) {
-        let handles: Vec<_> = (0..self.boards.len())
-            .map(|_| {
-                let mut board = self.boards.pop().unwrap();
-                let input = self.input.clone();
+    /// Plays every board and returns the winning boards ordered by draw index
+    /// and then by board index.
+    fn wins(self) -> Vec<BingoWin> {
+        let input = self.input;
+        let handles: Vec<_> = self
+            .boards
+            .into_iter()
+            .enumerate()
+            .map(|(board, mut b)| {
+                let input = input.clone();
 
-                thread::spawn(move || board.play(&input))
+                thread::spawn(move || {
+                    b.play(&input).map(|(draw, last, sum_unmarked)| BingoWin {
+                        board,
+                        draw,
+                        last,
+                        sum_unmarked,
+                    })
+                })
             })
             .collect();
 
-        let mut winner = self.input.len();
-        let mut loser = 0;
+        let mut wins: Vec<BingoWin> = handles
+            .into_iter()
+            .filter_map(|h| h.join().unwrap())
+            .collect();
+        wins.sort_by_key(|w| (w.draw, w.board));
+
+        wins
+    }
+
+    /// Returns the first and the last board to win, resolving boards that
+    /// complete on the same draw with `tie_break`.
+    fn play(self, tie_break: TieBreak) -> Option<(BingoWin, BingoWin)> {
+        let wins = self.wins();
+        let first_draw = wins.first()?.draw;
+        let last_draw = wins.last()?.draw;
+
+        Some((
+            tie_break.pick(&wins, first_draw)?,
+            tie_break.pick(&wins, last_draw)?,
+        ))
+    }
+}
+
+/// How a bingo game is replayed on the terminal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplayMode {
+    /// Wait for the user after every draw.
+    Step,
+    /// Print every draw without pausing.
+    Transcript,
+}
+
+impl FromStr for ReplayMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "step" => Ok(Self::Step),
+            "transcript" => Ok(Self::Transcript),
+            x => Err(format!("Unknown replay mode: {}", x)),
+        }
+    }
+}
+
+/// Steps through a bingo game one draw at a time, keeping the board state
+/// around for rendering. Boards stop being marked once they have won.
+struct BingoReplay {
+    input: Vec<u32>,
+    boards: Vec<BingoBoard>,
+    wins: Vec<Option<BingoWin>>,
+    draw: usize,
+    tie_break: TieBreak,
+}
 
-        let mut winner_result = 0;
-        let mut loser_result = 0;
+impl BingoReplay {
+    fn new(bingo: BingoSubsystem, tie_break: TieBreak) -> Self {
+        Self {
+            wins: vec![None; bingo.boards.len()],
+            input: bingo.input,
+            boards: bingo.boards,
+            draw: 0,
+            tie_break,
+        }
+    }
 
-        for h in handles {
-            if let Some((idx, last, sum_unmarked)) = h.join().unwrap() {
-                if idx < winner {
-                    winner = idx;
-                    winner_result = last * sum_unmarked;
+    /// The wins so far, ordered like `BingoSubsystem::wins`.
+    fn wins(&self) -> Vec<BingoWin> {
+        let mut wins: Vec<BingoWin> = self.wins.iter().flatten().copied().collect();
+        wins.sort_by_key(|w| (w.draw, w.board));
+        wins
+    }
+
+    /// The first winner and, once the game is over, the last one, resolving
+    /// ties like `BingoSubsystem::play`.
+    fn picks(&self) -> (Option<BingoWin>, Option<BingoWin>) {
+        let wins = self.wins();
+        let first = wins
+            .first()
+            .and_then(|w| self.tie_break.pick(&wins, w.draw));
+        let last = match wins.last() {
+            Some(w) if self.is_finished() => self.tie_break.pick(&wins, w.draw),
+            _ => None,
+        };
+        (first, last)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.draw >= self.input.len() || self.wins.iter().all(|w| w.is_some())
+    }
+
+    /// Draws the next number and returns the boards that won on it.
+    fn step(&mut self) -> Option<Vec<BingoWin>> {
+        if self.is_finished() {
+            return None;
+        }
+
+        let num = self.input[self.draw];
+        let mut winners = Vec::new();
+        for (board, b) in self.boards.iter_mut().enumerate() {
+            if self.wins[board].is_some() {
+                continue;
+            }
+            if b.check(&num) {
+                let win = BingoWin {
+                    board,
+                    draw: self.draw,
+                    last: num,
+                    sum_unmarked: b.data.keys().sum(),
+                };
+                self.wins[board] = Some(win);
+                winners.push(win);
+            }
+        }
+        self.draw += 1;
+
+        Some(winners)
+    }
+
+    /// Renders the state after the latest draw, announcing `winners`.
+    fn render(&self, winners: &[BingoWin], color: bool) -> String {
+        let mut frame = format!(
+            "Draw {}/{}: {}\n",
+            self.draw,
+            self.input.len(),
+            self.input[self.draw - 1]
+        );
+        for w in winners {
+            frame += &format!("Board {} wins with score {}\n", w.board, w.score());
+        }
+        let (first, last) = self.picks();
+        if let Some(w) = first.filter(|w| w.draw + 1 == self.draw) {
+            frame += &format!("First winner: board {} with score {}\n", w.board, w.score());
+        }
+        if let Some(w) = last.filter(|w| w.draw + 1 == self.draw) {
+            frame += &format!("Last winner: board {} with score {}\n", w.board, w.score());
+        }
+
+        for (board, b) in self.boards.iter().enumerate() {
+            frame += &match self.wins[board] {
+                Some(w) => format!("\nBoard {} (won on draw {})\n", board, w.draw + 1),
+                None => format!("\nBoard {}\n", board),
+            };
+            frame += &b.render(color);
+            frame += "\n";
+        }
+
+        frame
+    }
+
+    fn run<R: BufRead, W: Write>(
+        &mut self,
+        mode: ReplayMode,
+        color: bool,
+        mut input: R,
+        mut output: W,
+    ) -> io::Result<()> {
+        let mut mode = mode;
+        while let Some(winners) = self.step() {
+            writeln!(output, "{}", self.render(&winners, color))?;
+
+            if mode == ReplayMode::Step && !self.is_finished() {
+                write!(output, "[enter] step, [c] continue, [q] quit: ")?;
+                output.flush()?;
+
+                let mut cmd = String::new();
+                if input.read_line(&mut cmd)? == 0 {
+                    break;
                 }
-                if idx > loser {
-                    loser = idx;
-                    loser_result = last * sum_unmarked;
+                match cmd.trim() {
+                    "c" => mode = ReplayMode::Transcript,
+                    "q" => break,
+                    _ => {}
                 }
             }
         }
 
-        (winner_result, loser_result)
+        Ok(())
     }
 }
 
-impl Challenge <String>for Day4 {
+impl Challenge<String> for Day4 {
     fn new(input_file: &str) -> Self {
         Self {
             data: Self::load(input_file).unwrap(),
+            tie_break: TieBreak::FirstBoard,
+            replay: None,
+            color: true,
         }
     }
 
     fn run(&self, part: u32) -> Result<String, String> {
+        if let Some(mode) = self.replay {
+            self.replay(mode)?;
+        }
+
         match part {
             1 => self.run_part_one(),
             2 => self.run_part_two(),
@@ -134,16 +394,35 @@ impl Challenge <String>for Day4 {
 }
 
 impl Day4 {
+    pub fn with_tie_break(mut self, tie_break: TieBreak) -> Self {
+        self.tie_break = tie_break;
+        self
+    }
+
+    pub fn with_replay(mut self, replay: Option<ReplayMode>, color: bool) -> Self {
+        self.replay = replay;
+        self.color = color;
+        self
+    }
+
+    fn replay(&self, mode: ReplayMode) -> Result<(), String> {
+        let mut replay = BingoReplay::new(BingoSubsystem::from(&self.data[..]), self.tie_break);
+        let stdin = io::stdin();
+        replay
+            .run(mode, self.color, stdin.lock(), io::stdout())
+            .map_err(|e| format!("Error during replay: {}", e))
+    }
+
     fn run_part_one(&self) -> Result<String, String> {
         let bingo = BingoSubsystem::from(&self.data[..]);
-        let (result, _) = bingo.play();
-        Ok(format!("{:#?}", result))
+        let (winner, _) = bingo.play(self.tie_break).ok_or("No board has won")?;
+        Ok(format!("{:#?}", winner.score()))
     }
 
     fn run_part_two(&self) -> Result<String, String> {
         let bingo = BingoSubsystem::from(&self.data[..]);
-        let (_, result) = bingo.play();
-        Ok(format!("{:#?}", result))
+        let (_, loser) = bingo.play(self.tie_break).ok_or("No board has won")?;
+        Ok(format!("{:#?}", loser.score()))
     }
 }
 
@@ -228,8 +507,173 @@ mod tests {
     #[test]
     fn test_bingo_subsystem_play() {
         let bingo = BingoSubsystem::from(&get_input()[..]);
-        let (win, lose) = bingo.play();
-        assert_eq!(win, 4512);
-        assert_eq!(lose, 1924);
+        let (win, lose) = bingo.play(TieBreak::FirstBoard).unwrap();
+        assert_eq!(win.score(), 4512);
+        assert_eq!(win.board, 2);
+        assert_eq!(lose.score(), 1924);
+        assert_eq!(lose.board, 1);
+    }
+
+    fn get_tied_input() -> Vec<String> {
+        vec![
+            String::from("1,2,3,4,5,6,7,8,9,10"),
+            String::from(""),
+            String::from("1 2 3 4 5"),
+            String::from("30 31 32 33 34"),
+            String::from("35 36 37 38 39"),
+            String::from("40 41 42 43 44"),
+            String::from("45 46 47 48 49"),
+            String::from(""),
+            String::from("50 51 52 53 54"),
+            String::from("5 4 3 2 1"),
+            String::from("55 56 57 58 59"),
+            String::from("60 61 62 63 64"),
+            String::from("65 66 67 68 69"),
+            String::from(""),
+            String::from("6 7 8 9 10"),
+            String::from("70 71 72 73 74"),
+            String::from("75 76 77 78 79"),
+            String::from("80 81 82 83 84"),
+            String::from("85 86 87 88 89"),
+        ]
+    }
+
+    #[test]
+    fn test_bingo_subsystem_wins() {
+        let bingo = BingoSubsystem::from(&get_tied_input()[..]);
+        let boards: Vec<(usize, usize)> = bingo.wins().iter().map(|w| (w.draw, w.board)).collect();
+        assert_eq!(boards, vec![(4, 0), (4, 1), (9, 2)]);
+    }
+
+    #[test]
+    fn test_bingo_subsystem_play_tie_break() {
+        let bingo = BingoSubsystem::from(&get_tied_input()[..]);
+        let (win, lose) = bingo.play(TieBreak::FirstBoard).unwrap();
+        assert_eq!(win.board, 0);
+        assert_eq!(win.score(), 5 * (30..50).sum::<u32>());
+        assert_eq!(lose.board, 2);
+
+        let bingo = BingoSubsystem::from(&get_tied_input()[..]);
+        let (win, lose) = bingo.play(TieBreak::LastBoard).unwrap();
+        assert_eq!(win.board, 1);
+        assert_eq!(win.score(), 5 * (50..70).sum::<u32>());
+        assert_eq!(lose.board, 2);
+    }
+
+    #[test]
+    fn test_tie_break_from_str() {
+        assert_eq!(TieBreak::from_str("first"), Ok(TieBreak::FirstBoard));
+        assert_eq!(TieBreak::from_str("last"), Ok(TieBreak::LastBoard));
+        assert!(TieBreak::from_str("middle").is_err());
+    }
+
+    #[test]
+    fn test_bingo_board_render() {
+        let mut board = BingoBoard::from(&get_input()[2..7]);
+        board.check(&13);
+        board.check(&8);
+
+        let expected = "22  13* 17  11   0\n 8*  2  23   4  24\n21   9  14  16   7\n 6  10   3  18   5\n 1  12  20  15  19";
+        assert_eq!(board.render(false), expected);
+        assert!(board.render(true).contains("\x1b[1;32m13\x1b[0m"));
+    }
+
+    #[test]
+    fn test_bingo_replay_step() {
+        let mut replay =
+            BingoReplay::new(BingoSubsystem::from(&get_input()[..]), TieBreak::FirstBoard);
+        for _ in 0..11 {
+            assert_eq!(replay.step(), Some(vec![]));
+        }
+
+        let winners = replay.step().unwrap();
+        assert_eq!(winners.len(), 1);
+        assert_eq!(winners[0].board, 2);
+        assert_eq!(winners[0].score(), 4512);
+
+        let frame = replay.render(&winners, false);
+        assert!(frame.starts_with(
+            "Draw 12/27: 24\nBoard 2 wins with score 4512\nFirst winner: board 2 with score 4512\n"
+        ));
+        assert!(frame.contains("Board 2 (won on draw 12)\n14* 21* 17* 24*  4*"));
+
+        // The winning board is frozen, 10 is drawn next but stays unmarked.
+        replay.step().unwrap();
+        assert!(replay.boards[1].is_marked(&10));
+        assert!(!replay.boards[2].is_marked(&10));
+    }
+
+    #[test]
+    fn test_bingo_replay_tie_break() {
+        for tie_break in [TieBreak::FirstBoard, TieBreak::LastBoard].iter() {
+            let (win, lose) = BingoSubsystem::from(&get_tied_input()[..])
+                .play(*tie_break)
+                .unwrap();
+            let mut replay =
+                BingoReplay::new(BingoSubsystem::from(&get_tied_input()[..]), *tie_break);
+            let mut output = Vec::new();
+            replay
+                .run(ReplayMode::Transcript, false, &b""[..], &mut output)
+                .unwrap();
+
+            let transcript = String::from_utf8(output).unwrap();
+            assert_eq!(replay.picks(), (Some(win), Some(lose)));
+            assert!(transcript.contains(&format!(
+                "First winner: board {} with score {}\n",
+                win.board,
+                win.score()
+            )));
+            assert!(transcript.contains(&format!(
+                "Last winner: board {} with score {}\n",
+                lose.board,
+                lose.score()
+            )));
+        }
+    }
+
+    #[test]
+    fn test_bingo_replay_transcript() {
+        let mut replay =
+            BingoReplay::new(BingoSubsystem::from(&get_input()[..]), TieBreak::FirstBoard);
+        let mut output = Vec::new();
+        replay
+            .run(ReplayMode::Transcript, false, &b""[..], &mut output)
+            .unwrap();
+
+        let transcript = String::from_utf8(output).unwrap();
+        assert_eq!(transcript.matches("Draw ").count(), 15);
+        assert!(transcript.contains("Board 1 wins with score 1924"));
+        assert!(transcript.contains("Last winner: board 1 with score 1924\n"));
+        assert!(!transcript.contains("[enter]"));
+    }
+
+    #[test]
+    fn test_bingo_replay_controls() {
+        let mut replay =
+            BingoReplay::new(BingoSubsystem::from(&get_input()[..]), TieBreak::FirstBoard);
+        let mut output = Vec::new();
+        replay
+            .run(ReplayMode::Step, false, &b"\nq\n"[..], &mut output)
+            .unwrap();
+        let transcript = String::from_utf8(output).unwrap();
+        assert_eq!(transcript.matches("Draw ").count(), 2);
+
+        let mut replay =
+            BingoReplay::new(BingoSubsystem::from(&get_input()[..]), TieBreak::FirstBoard);
+        let mut output = Vec::new();
+        replay
+            .run(ReplayMode::Step, false, &b"\nc\n"[..], &mut output)
+            .unwrap();
+        let transcript = String::from_utf8(output).unwrap();
+        assert_eq!(transcript.matches("Draw ").count(), 15);
+        assert_eq!(transcript.matches("[enter]").count(), 2);
+    }
+
+    #[test]
+    fn test_bingo_subsystem_play_no_winner() {
+        let mut input = get_tied_input();
+        input[0] = String::from("1,2,3");
+        let bingo = BingoSubsystem::from(&input[..]);
+        assert_eq!(bingo.play(TieBreak::FirstBoard), None);
     }
 }
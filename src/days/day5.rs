@@ -1,67 +1,598 @@
 use crate::utils::challenge::Challenge;
+use ndarray::Array2;
 use regex::Regex;
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fmt::Debug;
+use std::fs;
+use std::io::{self, Write};
+use std::str::FromStr;
 
+/// Largest number of cells a dense grid may allocate before the sparse map is
+/// used instead.
+const DENSE_LIMIT: usize = 1 << 24;
+
+/// Largest number of cells rendered as text, enough for a 1000 by 1000 map.
+const ASCII_LIMIT: usize = 1 << 20;
+
+/// Number of cells in the box spanned by two corners.
+fn area(min: (i32, i32), max: (i32, i32)) -> usize {
+    let width = (max.0 as i64 - min.0 as i64 + 1) as usize;
+    let height = (max.1 as i64 - min.1 as i64 + 1) as usize;
+    width.saturating_mul(height)
+}
+
+trait VentStorage: Debug {
+    fn add(&mut self, pos: (i32, i32));
+    fn get(&self, pos: (i32, i32)) -> u32;
+    /// Iterates over all cells covered by at least one vent.
+    fn iter(&self) -> Box<dyn Iterator<Item = ((i32, i32), u32)> + '_>;
+
+    fn count_overlaps(&self) -> usize {
+        self.iter().filter(|(_, v)| *v > 1).count()
+    }
+}
+
+impl VentStorage for HashMap<(i32, i32), u32> {
+    fn add(&mut self, pos: (i32, i32)) {
+        *self.entry(pos).or_insert(0) += 1;
+    }
+
+    fn get(&self, pos: (i32, i32)) -> u32 {
+        *HashMap::get(self, &pos).unwrap_or(&0)
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = ((i32, i32), u32)> + '_> {
+        Box::new(HashMap::iter(self).map(|(k, v)| (*k, *v)))
+    }
+}
+
+/// Vent counts for every cell of a bounding box, indexed by `[y, x]`.
 #[derive(Debug, PartialEq)]
+struct DenseGrid {
+    origin: (i32, i32),
+    cells: Array2<u32>,
+}
+
+impl DenseGrid {
+    fn new(min: (i32, i32), max: (i32, i32)) -> Self {
+        let width = (max.0 - min.0 + 1) as usize;
+        let height = (max.1 - min.1 + 1) as usize;
+        Self {
+            origin: min,
+            cells: Array2::zeros((height, width)),
+        }
+    }
+
+    fn fits(min: (i32, i32), max: (i32, i32)) -> bool {
+        area(min, max) <= DENSE_LIMIT
+    }
+
+    fn index(&self, pos: (i32, i32)) -> Option<(usize, usize)> {
+        let x = usize::try_from(pos.0 - self.origin.0).ok()?;
+        let y = usize::try_from(pos.1 - self.origin.1).ok()?;
+        let (height, width) = self.cells.dim();
+        if x < width && y < height {
+            Some((y, x))
+        } else {
+            None
+        }
+    }
+}
+
+impl VentStorage for DenseGrid {
+    fn add(&mut self, pos: (i32, i32)) {
+        let idx = self
+            .index(pos)
+            .unwrap_or_else(|| panic!("Vent {:?} outside of the dense grid", pos));
+        self.cells[idx] += 1;
+    }
+
+    fn get(&self, pos: (i32, i32)) -> u32 {
+        self.index(pos).map_or(0, |idx| self.cells[idx])
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = ((i32, i32), u32)> + '_> {
+        Box::new(
+            self.cells
+                .indexed_iter()
+                .filter(|(_, v)| **v > 0)
+                .map(move |((y, x), v)| ((self.origin.0 + x as i32, self.origin.1 + y as i32), *v)),
+        )
+    }
+
+    fn count_overlaps(&self) -> usize {
+        self.cells.iter().filter(|&v| *v > 1).count()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Orientation {
+    Horizontal,
+    Vertical,
+    Diagonal,
+}
+
+impl Orientation {
+    fn of(start: (i32, i32), end: (i32, i32)) -> Result<Self, String> {
+        let dx = i32::abs(end.0 - start.0);
+        let dy = i32::abs(end.1 - start.1);
+        if dy == 0 {
+            Ok(Self::Horizontal)
+        } else if dx == 0 {
+            Ok(Self::Vertical)
+        } else if dx == dy {
+            Ok(Self::Diagonal)
+        } else {
+            Err(format!(
+                "Segment {:?} -> {:?} is neither axis-aligned nor diagonal",
+                start, end
+            ))
+        }
+    }
+}
+
+/// Which segments of the input are added to the ocean floor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SegmentFilter {
+    /// Only horizontal and vertical segments.
+    Straight,
+    /// Only 45° diagonal segments.
+    Diagonal,
+    All,
+}
+
+impl FromStr for SegmentFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "straight" => Ok(Self::Straight),
+            "diagonal" => Ok(Self::Diagonal),
+            "all" => Ok(Self::All),
+            x => Err(format!("Unknown segment filter: {}", x)),
+        }
+    }
+}
+
+impl SegmentFilter {
+    fn accepts(&self, orientation: Orientation) -> bool {
+        match self {
+            Self::Straight => orientation != Orientation::Diagonal,
+            Self::Diagonal => orientation == Orientation::Diagonal,
+            Self::All => true,
+        }
+    }
+}
+
+#[derive(Debug)]
 struct OceanFloor {
-    vents: HashMap<(i32, i32), u32>,
+    vents: Box<dyn VentStorage>,
 }
 
 impl OceanFloor {
     fn new() -> Self {
         Self {
-            vents: HashMap::new(),
+            vents: Box::new(HashMap::new()),
+        }
+    }
+
+    /// Uses a dense grid if the bounding box is small enough, otherwise falls
+    /// back to a sparse map.
+    fn with_bounds(min: (i32, i32), max: (i32, i32)) -> Self {
+        if DenseGrid::fits(min, max) {
+            Self {
+                vents: Box::new(DenseGrid::new(min, max)),
+            }
+        } else {
+            Self::new()
         }
     }
 
-    fn add_vent(&mut self, start: (i32, i32), end: (i32, i32)) {
+    fn add_vent(&mut self, start: (i32, i32), end: (i32, i32)) -> Result<(), String> {
+        Orientation::of(start, end)?;
+
         let steps = i32::max(i32::abs(start.0 - end.0), i32::abs(start.1 - end.1));
         let dx = i32::signum(end.0 - start.0);
         let dy = i32::signum(end.1 - start.1);
         for i in 0..=steps {
-            *self
-                .vents
-                .entry((start.0 + i * dx, start.1 + i * dy))
-                .or_insert(0) += 1;
+            self.vents.add((start.0 + i * dx, start.1 + i * dy));
         }
+
+        Ok(())
     }
 
     fn count_overlaps(&self) -> usize {
-        self.vents.values().filter(|&v| *v > 1).count()
+        self.vents.count_overlaps()
+    }
+
+    /// Returns the area covered by vents, extended to include the origin like
+    /// the diagrams in the puzzle.
+    fn extent(&self) -> Option<((i32, i32), (i32, i32))> {
+        self.vents.iter().fold(None, |acc, (p, _)| match acc {
+            None => Some(((p.0.min(0), p.1.min(0)), (p.0.max(0), p.1.max(0)))),
+            Some((min, max)) => Some((
+                (min.0.min(p.0), min.1.min(p.1)),
+                (max.0.max(p.0), max.1.max(p.1)),
+            )),
+        })
+    }
+
+    /// Renders the vent counts like the puzzle does, using `.` for cells
+    /// without vents and `#` for more than nine overlapping vents.
+    fn render_ascii(&self) -> String {
+        let (min, max) = match self.extent() {
+            Some(extent) => extent,
+            None => return String::new(),
+        };
+
+        (min.1..=max.1)
+            .map(|y| {
+                (min.0..=max.0)
+                    .map(|x| match self.vents.get((x, y)) {
+                        0 => '.',
+                        v if v > 9 => '#',
+                        v => std::char::from_digit(v, 10).unwrap(),
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Renders the vent counts as a binary PGM heatmap, scaled so the highest
+    /// count is white.
+    fn render_pgm(&self) -> Result<Vec<u8>, String> {
+        let (min, max) = self.extent().unwrap_or(((0, 0), (0, 0)));
+        if !DenseGrid::fits(min, max) {
+            return Err(format!(
+                "Vent map {:?} -> {:?} is too large to render",
+                min, max
+            ));
+        }
+
+        let width = max.0 - min.0 + 1;
+        let height = max.1 - min.1 + 1;
+        let peak = self.vents.iter().map(|(_, v)| v).max().unwrap_or(0).max(1);
+
+        let mut image = format!("P5\n{} {}\n255\n", width, height).into_bytes();
+        for y in min.1..=max.1 {
+            for x in min.0..=max.0 {
+                image.push((self.vents.get((x, y)) * 255 / peak) as u8);
+            }
+        }
+
+        Ok(image)
     }
 }
 
 impl From<&[String]> for OceanFloor {
     fn from(input: &[String]) -> Self {
-        let pat = Regex::new(r"^(\d+),(\d+)\s->\s(\d+),(\d+)$").unwrap();
-        let mut floor = OceanFloor::new();
+        Self::from_filtered(input, SegmentFilter::All).unwrap()
+    }
+}
+
+impl OceanFloor {
+    /// Builds the ocean floor from the segments accepted by `filter`. Fails if
+    /// any segment of the input is neither axis-aligned nor diagonal.
+    fn from_filtered(input: &[String], filter: SegmentFilter) -> Result<Self, String> {
+        Self::from_segments(&parse_segments(input, filter)?)
+    }
+
+    fn from_segments(segments: &[Segment]) -> Result<Self, String> {
+        let mut floor = match bounds(segments) {
+            Some((min, max)) => OceanFloor::with_bounds(min, max),
+            None => OceanFloor::new(),
+        };
+
+        for (start, end) in segments {
+            floor.add_vent(*start, *end)?;
+        }
+
+        Ok(floor)
+    }
+}
+
+type Segment = ((i32, i32), (i32, i32));
+
+fn parse_segments(input: &[String], filter: SegmentFilter) -> Result<Vec<Segment>, String> {
+    let pat = Regex::new(r"^(\d+),(\d+)\s->\s(\d+),(\d+)$").unwrap();
+
+    let mut segments = Vec::new();
+    for l in input {
+        let tokens = pat.captures(l).unwrap();
+        let x1 = tokens.get(1).unwrap().as_str().parse::<i32>().unwrap();
+        let y1 = tokens.get(2).unwrap().as_str().parse::<i32>().unwrap();
+        let x2 = tokens.get(3).unwrap().as_str().parse::<i32>().unwrap();
+        let y2 = tokens.get(4).unwrap().as_str().parse::<i32>().unwrap();
+        if filter.accepts(Orientation::of((x1, y1), (x2, y2))?) {
+            segments.push(((x1, y1), (x2, y2)));
+        }
+    }
+
+    Ok(segments)
+}
+
+/// Returns the bounding box of all segments, if there are any.
+fn bounds(segments: &[Segment]) -> Option<((i32, i32), (i32, i32))> {
+    let points = segments.iter().flat_map(|(s, e)| [s, e]);
+    let min = points.clone().fold(None, |acc: Option<(i32, i32)>, p| {
+        Some(acc.map_or(*p, |m| (m.0.min(p.0), m.1.min(p.1))))
+    })?;
+    let max = points.fold(min, |m, p| (m.0.max(p.0), m.1.max(p.1)));
+
+    Some((min, max))
+}
+
+/// The infinite line a segment lies on, identified by the coordinate that is
+/// constant along it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Line {
+    /// `y`
+    Horizontal(i64),
+    /// `x`
+    Vertical(i64),
+    /// `y - x`
+    Diagonal(i64),
+    /// `x + y`
+    AntiDiagonal(i64),
+}
+
+impl Line {
+    fn of(start: (i32, i32), end: (i32, i32)) -> Self {
+        let (x1, y1) = (start.0 as i64, start.1 as i64);
+        let (x2, y2) = (end.0 as i64, end.1 as i64);
+        if y1 == y2 {
+            Self::Horizontal(y1)
+        } else if x1 == x2 {
+            Self::Vertical(x1)
+        } else if x2 - x1 == y2 - y1 {
+            Self::Diagonal(y1 - x1)
+        } else {
+            Self::AntiDiagonal(x1 + y1)
+        }
+    }
+
+    fn through(p: (i64, i64)) -> [Self; 4] {
+        [
+            Self::Horizontal(p.1),
+            Self::Vertical(p.0),
+            Self::Diagonal(p.1 - p.0),
+            Self::AntiDiagonal(p.0 + p.1),
+        ]
+    }
+
+    /// Position of a point on this line along the line.
+    fn position(&self, p: (i64, i64)) -> i64 {
+        match self {
+            Self::Vertical(_) => p.1,
+            _ => p.0,
+        }
+    }
+
+    /// Returns the lattice point where two non-parallel lines cross.
+    fn crossing(&self, other: &Self) -> Option<(i64, i64)> {
+        use Line::*;
+
+        match (*self, *other) {
+            (Horizontal(y), Vertical(x)) | (Vertical(x), Horizontal(y)) => Some((x, y)),
+            (Horizontal(y), Diagonal(k)) | (Diagonal(k), Horizontal(y)) => Some((y - k, y)),
+            (Horizontal(y), AntiDiagonal(s)) | (AntiDiagonal(s), Horizontal(y)) => Some((s - y, y)),
+            (Vertical(x), Diagonal(k)) | (Diagonal(k), Vertical(x)) => Some((x, x + k)),
+            (Vertical(x), AntiDiagonal(s)) | (AntiDiagonal(s), Vertical(x)) => Some((x, s - x)),
+            (Diagonal(k), AntiDiagonal(s)) | (AntiDiagonal(s), Diagonal(k)) => {
+                if (s - k) % 2 == 0 {
+                    Some(((s - k) / 2, (s + k) / 2))
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A segment as the closed range `lo..=hi` of positions on its line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Span {
+    line: Line,
+    lo: i64,
+    hi: i64,
+}
+
+impl Span {
+    fn of(start: (i32, i32), end: (i32, i32)) -> Self {
+        let line = Line::of(start, end);
+        let a = line.position((start.0 as i64, start.1 as i64));
+        let b = line.position((end.0 as i64, end.1 as i64));
+        Self {
+            line,
+            lo: a.min(b),
+            hi: a.max(b),
+        }
+    }
+
+    fn contains(&self, p: (i64, i64)) -> bool {
+        (self.lo..=self.hi).contains(&self.line.position(p))
+    }
+}
+
+/// Merges ranges on one line into the sorted, disjoint ranges covered at
+/// least twice.
+fn covered_twice(ranges: &[(i64, i64)]) -> Vec<(i64, i64)> {
+    let mut events: Vec<(i64, i32)> = ranges
+        .iter()
+        .flat_map(|&(lo, hi)| [(lo, 1), (hi + 1, -1)])
+        .collect();
+    events.sort_unstable();
+
+    let mut overlaps = Vec::new();
+    let mut depth = 0;
+    let mut start = None;
+    for (pos, delta) in events {
+        depth += delta;
+        match start {
+            None if depth >= 2 => start = Some(pos),
+            Some(s) if depth < 2 => {
+                overlaps.push((s, pos - 1));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    overlaps
+}
+
+/// Counts the lattice points covered by at least two segments without
+/// rasterizing them. Collinear segments are merged per line, crossings of
+/// segments on different lines are found pairwise, so the cost depends on the
+/// number of segments rather than on their length.
+fn count_overlaps_analytic(segments: &[Segment]) -> usize {
+    let spans: Vec<Span> = segments.iter().map(|(s, e)| Span::of(*s, *e)).collect();
+
+    let mut by_line: HashMap<Line, Vec<(i64, i64)>> = HashMap::new();
+    for span in &spans {
+        by_line
+            .entry(span.line)
+            .or_default()
+            .push((span.lo, span.hi));
+    }
+    let overlaps: HashMap<Line, Vec<(i64, i64)>> = by_line
+        .into_iter()
+        .map(|(line, ranges)| (line, covered_twice(&ranges)))
+        .filter(|(_, ranges)| !ranges.is_empty())
+        .collect();
+
+    let mut crossings = HashSet::new();
+    for (i, a) in spans.iter().enumerate() {
+        for b in &spans[i + 1..] {
+            if let Some(p) = a.line.crossing(&b.line) {
+                if a.contains(p) && b.contains(p) {
+                    crossings.insert(p);
+                }
+            }
+        }
+    }
+
+    // Number of merged collinear overlaps a point is part of.
+    let overlap_count = |p: &(i64, i64)| {
+        Line::through(*p)
+            .iter()
+            .filter(|line| {
+                overlaps.get(line).is_some_and(|ranges| {
+                    let pos = line.position(*p);
+                    ranges
+                        .binary_search_by(|&(lo, hi)| {
+                            if hi < pos {
+                                Ordering::Less
+                            } else if lo > pos {
+                                Ordering::Greater
+                            } else {
+                                Ordering::Equal
+                            }
+                        })
+                        .is_ok()
+                })
+            })
+            .count() as i64
+    };
+
+    let collinear: i64 = overlaps
+        .values()
+        .flatten()
+        .map(|(lo, hi)| hi - lo + 1)
+        .sum();
+
+    // A point on overlaps of several lines is always a crossing as well, so
+    // correcting the crossings for those counts every point exactly once.
+    let corrections: i64 = crossings
+        .iter()
+        .map(|p| match overlap_count(p) {
+            0 => 1,
+            n => 1 - n,
+        })
+        .sum();
+
+    (collinear + corrections) as usize
+}
+
+/// Output format of the vent map.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderFormat {
+    Ascii,
+    Pgm,
+}
+
+impl FromStr for RenderFormat {
+    type Err = String;
 
-        for l in input {
-            let tokens = pat.captures(l).unwrap();
-            let x1 = tokens.get(1).unwrap().as_str().parse::<i32>().unwrap();
-            let y1 = tokens.get(2).unwrap().as_str().parse::<i32>().unwrap();
-            let x2 = tokens.get(3).unwrap().as_str().parse::<i32>().unwrap();
-            let y2 = tokens.get(4).unwrap().as_str().parse::<i32>().unwrap();
-            floor.add_vent((x1, y1), (x2, y2));
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ascii" => Ok(Self::Ascii),
+            "pgm" => Ok(Self::Pgm),
+            x => Err(format!("Unknown render format: {}", x)),
+        }
+    }
+}
+
+impl RenderFormat {
+    /// Largest number of cells a map may have to be rendered in this format.
+    fn limit(&self) -> usize {
+        match self {
+            Self::Ascii => ASCII_LIMIT,
+            Self::Pgm => DENSE_LIMIT,
+        }
+    }
+
+    /// Checks the size of the map the segments draw, including the origin,
+    /// before any of it is built.
+    fn check_size(&self, segments: &[Segment]) -> Result<(), String> {
+        let (min, max) = match bounds(segments) {
+            Some((min, max)) => ((min.0.min(0), min.1.min(0)), (max.0.max(0), max.1.max(0))),
+            None => return Ok(()),
+        };
+        if area(min, max) > self.limit() {
+            return Err(format!(
+                "Vent map {:?} -> {:?} is too large to render as {:?}",
+                min, max, self
+            ));
         }
 
-        floor
+        Ok(())
+    }
+}
+
+/// Rasterizes the segments if they fit into a dense grid and counts the
+/// overlaps analytically otherwise.
+fn count_overlaps(segments: &[Segment]) -> Result<usize, String> {
+    match bounds(segments) {
+        Some((min, max)) if !DenseGrid::fits(min, max) => Ok(count_overlaps_analytic(segments)),
+        _ => Ok(OceanFloor::from_segments(segments)?.count_overlaps()),
     }
 }
 
 pub struct Day5 {
     data: Vec<String>,
+    filter: Option<SegmentFilter>,
+    render: Option<RenderFormat>,
+    render_to: Option<String>,
 }
 
-impl Challenge <String>for Day5 {
+impl Challenge<String> for Day5 {
     fn new(input_file: &str) -> Self {
         Self {
             data: Self::load(input_file).unwrap(),
+            filter: None,
+            render: None,
+            render_to: None,
         }
     }
 
     fn run(&self, part: u32) -> Result<String, String> {
         match part {
+            1 => self.run_part_one(),
             2 => self.run_part_two(),
             x => unimplemented!(
                 "Invalid part {} for Day {}",
@@ -73,9 +604,49 @@ impl Challenge <String>for Day5 {
 }
 
 impl Day5 {
+    /// Overrides the segment filter implied by the part.
+    pub fn with_filter(mut self, filter: Option<SegmentFilter>) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Renders the vent map to `render_to`, or to stdout if no file is given.
+    pub fn with_render(mut self, render: Option<RenderFormat>, render_to: Option<String>) -> Self {
+        self.render = render;
+        self.render_to = render_to;
+        self
+    }
+
+    fn render(&self, segments: &[Segment], format: RenderFormat) -> Result<(), String> {
+        format.check_size(segments)?;
+        let floor = OceanFloor::from_segments(segments)?;
+        let image = match format {
+            RenderFormat::Ascii => (floor.render_ascii() + "\n").into_bytes(),
+            RenderFormat::Pgm => floor.render_pgm()?,
+        };
+
+        match &self.render_to {
+            Some(path) => fs::write(path, image),
+            None => io::stdout().write_all(&image),
+        }
+        .map_err(|e| format!("Error while rendering: {}", e))
+    }
+
+    fn solve(&self, filter: SegmentFilter) -> Result<String, String> {
+        let segments = parse_segments(&self.data[..], self.filter.unwrap_or(filter))?;
+        if let Some(format) = self.render {
+            self.render(&segments, format)?;
+        }
+
+        Ok(format!("{:#?}", count_overlaps(&segments)?))
+    }
+
+    fn run_part_one(&self) -> Result<String, String> {
+        self.solve(SegmentFilter::Straight)
+    }
+
     fn run_part_two(&self) -> Result<String, String> {
-        let floor = OceanFloor::from(&self.data[..]);
-        Ok(format!("{:#?}", floor.count_overlaps()))
+        self.solve(SegmentFilter::All)
     }
 }
 
@@ -83,31 +654,39 @@ impl Day5 {
 mod tests {
     use super::*;
 
+    fn cells(floor: &OceanFloor) -> HashMap<(i32, i32), u32> {
+        floor.vents.iter().collect()
+    }
+
     #[test]
     fn test_add_line() {
-        let expected = OceanFloor {
-            vents: HashMap::from([
-                ((0, 9), 2),
-                ((1, 9), 2),
-                ((2, 9), 2),
-                ((3, 9), 1),
-                ((4, 9), 1),
-                ((5, 9), 1),
-                ((9, 4), 1),
-                ((8, 4), 1),
-                ((7, 4), 1),
-                ((6, 4), 1),
-                ((5, 4), 1),
-                ((4, 4), 1),
-                ((3, 4), 1),
-            ]),
-        };
+        let expected = HashMap::from([
+            ((0, 9), 2),
+            ((1, 9), 2),
+            ((2, 9), 2),
+            ((3, 9), 1),
+            ((4, 9), 1),
+            ((5, 9), 1),
+            ((9, 4), 1),
+            ((8, 4), 1),
+            ((7, 4), 1),
+            ((6, 4), 1),
+            ((5, 4), 1),
+            ((4, 4), 1),
+            ((3, 4), 1),
+        ]);
 
         let mut floor = OceanFloor::new();
-        floor.add_vent((0, 9), (5, 9));
-        floor.add_vent((0, 9), (2, 9));
-        floor.add_vent((9, 4), (3, 4));
-        assert_eq!(expected, floor)
+        floor.add_vent((0, 9), (5, 9)).unwrap();
+        floor.add_vent((0, 9), (2, 9)).unwrap();
+        floor.add_vent((9, 4), (3, 4)).unwrap();
+        assert_eq!(expected, cells(&floor));
+
+        let mut floor = OceanFloor::with_bounds((0, 0), (9, 9));
+        floor.add_vent((0, 9), (5, 9)).unwrap();
+        floor.add_vent((0, 9), (2, 9)).unwrap();
+        floor.add_vent((9, 4), (3, 4)).unwrap();
+        assert_eq!(expected, cells(&floor));
     }
 
     #[test]
@@ -121,34 +700,32 @@ mod tests {
             "3,4 -> 1,4".to_string(),
         ];
 
-        let expected = OceanFloor {
-            vents: HashMap::from([
-                ((1, 4), 1),
-                ((1, 9), 2),
-                ((5, 9), 1),
-                ((5, 4), 1),
-                ((3, 9), 1),
-                ((2, 1), 1),
-                ((7, 3), 1),
-                ((7, 0), 1),
-                ((7, 2), 1),
-                ((2, 4), 1),
-                ((7, 4), 2),
-                ((6, 4), 1),
-                ((4, 9), 1),
-                ((2, 9), 2),
-                ((2, 2), 1),
-                ((9, 4), 1),
-                ((4, 4), 1),
-                ((8, 4), 1),
-                ((7, 1), 1),
-                ((0, 9), 2),
-                ((3, 4), 2),
-            ]),
-        };
+        let expected = HashMap::from([
+            ((1, 4), 1),
+            ((1, 9), 2),
+            ((5, 9), 1),
+            ((5, 4), 1),
+            ((3, 9), 1),
+            ((2, 1), 1),
+            ((7, 3), 1),
+            ((7, 0), 1),
+            ((7, 2), 1),
+            ((2, 4), 1),
+            ((7, 4), 2),
+            ((6, 4), 1),
+            ((4, 9), 1),
+            ((2, 9), 2),
+            ((2, 2), 1),
+            ((9, 4), 1),
+            ((4, 4), 1),
+            ((8, 4), 1),
+            ((7, 1), 1),
+            ((0, 9), 2),
+            ((3, 4), 2),
+        ]);
 
         let floor = OceanFloor::from(&input[..]);
-        assert_eq!(expected.vents, floor.vents);
+        assert_eq!(expected, cells(&floor));
     }
 
     #[test]
@@ -171,37 +748,311 @@ mod tests {
     }
 
     #[test]
-    fn test_add_diagonal() {
-        let expected = OceanFloor {
-            vents: HashMap::from([
-                ((0, 0), 1),
-                ((0, 8), 1),
-                ((1, 1), 1),
-                ((1, 7), 1),
-                ((2, 0), 1),
-                ((2, 2), 1),
-                ((2, 6), 1),
-                ((3, 1), 1),
-                ((3, 3), 1),
-                ((3, 5), 1),
-                ((4, 2), 1),
-                ((4, 4), 2),
-                ((5, 3), 2),
-                ((5, 5), 1),
-                ((6, 2), 1),
-                ((6, 4), 1),
-                ((6, 6), 1),
-                ((7, 1), 1),
-                ((7, 7), 1),
-                ((8, 0), 1),
-                ((8, 8), 1),
-            ]),
+    fn test_from_filtered() {
+        let input = get_example();
+
+        let floor = OceanFloor::from_filtered(&input[..], SegmentFilter::Straight).unwrap();
+        assert_eq!(5, floor.count_overlaps());
+        let floor = OceanFloor::from_filtered(&input[..], SegmentFilter::Diagonal).unwrap();
+        assert_eq!(4, floor.count_overlaps());
+        let floor = OceanFloor::from_filtered(&input[..], SegmentFilter::All).unwrap();
+        assert_eq!(12, floor.count_overlaps());
+    }
+
+    #[test]
+    fn test_segment_filter_from_str() {
+        assert_eq!(
+            Ok(SegmentFilter::Straight),
+            SegmentFilter::from_str("straight")
+        );
+        assert_eq!(
+            Ok(SegmentFilter::Diagonal),
+            SegmentFilter::from_str("diagonal")
+        );
+        assert_eq!(Ok(SegmentFilter::All), SegmentFilter::from_str("all"));
+        assert!(SegmentFilter::from_str("skewed").is_err());
+    }
+
+    fn get_example() -> Vec<String> {
+        vec![
+            String::from("0,9 -> 5,9"),
+            String::from("8,0 -> 0,8"),
+            String::from("9,4 -> 3,4"),
+            String::from("2,2 -> 2,1"),
+            String::from("7,0 -> 7,4"),
+            String::from("6,4 -> 2,0"),
+            String::from("0,9 -> 2,9"),
+            String::from("3,4 -> 1,4"),
+            String::from("0,0 -> 8,8"),
+            String::from("5,5 -> 8,2"),
+        ]
+    }
+
+    /// Generates pseudo-random straight and diagonal segments inside a
+    /// `size` x `size` box.
+    fn random_segments(seed: u64, count: usize, size: i32) -> Vec<Segment> {
+        let mut state = seed;
+        let mut next = move |n: i32| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((state >> 33) % n as u64) as i32
         };
 
+        (0..count)
+            .map(|_| {
+                let start = (next(size), next(size));
+                let len = next(size);
+                let (dx, dy) =
+                    [(1, 0), (0, 1), (1, 1), (1, -1), (-1, 0), (0, -1)][next(6) as usize];
+                let len = [size - 1 - start.0, start.0, size - 1 - start.1, start.1]
+                    .iter()
+                    .zip([dx > 0, dx < 0, dy > 0, dy < 0])
+                    .filter(|(_, moves)| *moves)
+                    .fold(len, |acc, (room, _)| acc.min(*room));
+                (start, (start.0 + len * dx, start.1 + len * dy))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_line_crossing() {
+        let h = Line::of((0, 4), (9, 4));
+        let v = Line::of((7, 0), (7, 9));
+        let d = Line::of((0, 0), (8, 8));
+        let a = Line::of((8, 0), (0, 8));
+        assert_eq!(Some((7, 4)), h.crossing(&v));
+        assert_eq!(Some((4, 4)), h.crossing(&d));
+        assert_eq!(Some((4, 4)), a.crossing(&h));
+        assert_eq!(Some((7, 7)), v.crossing(&d));
+        assert_eq!(Some((7, 1)), v.crossing(&a));
+        assert_eq!(Some((4, 4)), d.crossing(&a));
+        assert_eq!(None, Line::of((0, 1), (1, 2)).crossing(&a));
+        assert_eq!(None, h.crossing(&Line::of((0, 5), (9, 5))));
+    }
+
+    #[test]
+    fn test_covered_twice() {
+        assert_eq!(Vec::<(i64, i64)>::new(), covered_twice(&[(0, 5), (6, 9)]));
+        assert_eq!(vec![(5, 5)], covered_twice(&[(0, 5), (5, 9)]));
+        assert_eq!(
+            vec![(2, 3), (4, 8)],
+            covered_twice(&[(0, 3), (2, 8), (4, 9)])
+        );
+    }
+
+    #[test]
+    fn test_count_overlaps_analytic() {
+        let segments = parse_segments(&get_example(), SegmentFilter::Straight).unwrap();
+        assert_eq!(5, count_overlaps_analytic(&segments));
+        let segments = parse_segments(&get_example(), SegmentFilter::All).unwrap();
+        assert_eq!(12, count_overlaps_analytic(&segments));
+    }
+
+    #[test]
+    fn test_count_overlaps_analytic_matches_raster() {
+        for seed in 0..20 {
+            let segments = random_segments(seed, 60, 30);
+            let floor = OceanFloor::from_segments(&segments).unwrap();
+            assert_eq!(
+                floor.count_overlaps(),
+                count_overlaps_analytic(&segments),
+                "seed {}",
+                seed
+            );
+        }
+    }
+
+    #[test]
+    fn test_count_overlaps_large_coordinates() {
+        let segments = vec![
+            ((0, 0), (4_000_000, 4_000_000)),
+            ((1_000_000, 1_000_000), (5_000_000, 5_000_000)),
+            ((0, 4_000_000), (4_000_000, 0)),
+            ((2_000_000, 0), (2_000_000, 9_000_000)),
+            ((0, 8_000_000), (3_000_000, 8_000_000)),
+        ];
+        // The diagonals share 3_000_001 points, all crossings but the one at
+        // (2_000_000, 8_000_000) lie on that overlap.
+        assert_eq!(3_000_002, count_overlaps(&segments).unwrap());
+    }
+
+    #[test]
+    fn test_render_ascii() {
+        let floor = OceanFloor::from_filtered(&get_example(), SegmentFilter::Straight).unwrap();
+        let expected = [
+            ".......1..",
+            "..1....1..",
+            "..1....1..",
+            ".......1..",
+            ".112111211",
+            "..........",
+            "..........",
+            "..........",
+            "..........",
+            "222111....",
+        ];
+        assert_eq!(expected.join("\n"), floor.render_ascii());
+
+        let floor = OceanFloor::from_filtered(&get_example(), SegmentFilter::All).unwrap();
+        let expected = [
+            "1.1....11.",
+            ".111...2..",
+            "..2.1.111.",
+            "...1.2.2..",
+            ".112313211",
+            "...1.2....",
+            "..1...1...",
+            ".1.....1..",
+            "1.......1.",
+            "222111....",
+        ];
+        assert_eq!(expected.join("\n"), floor.render_ascii());
+
+        assert_eq!("", OceanFloor::new().render_ascii());
+    }
+
+    #[test]
+    fn test_render_ascii_many_overlaps() {
+        let mut floor = OceanFloor::new();
+        for _ in 0..10 {
+            floor.add_vent((1, 0), (2, 0)).unwrap();
+        }
+        floor.add_vent((2, 0), (2, 0)).unwrap();
+        assert_eq!(".##", floor.render_ascii());
+    }
+
+    #[test]
+    fn test_render_pgm() {
+        let mut floor = OceanFloor::new();
+        floor.add_vent((0, 0), (2, 0)).unwrap();
+        floor.add_vent((2, 0), (2, 1)).unwrap();
+
+        let mut expected = b"P5\n3 2\n255\n".to_vec();
+        expected.extend_from_slice(&[127, 127, 255, 0, 0, 127]);
+        assert_eq!(expected, floor.render_pgm().unwrap());
+
         let mut floor = OceanFloor::new();
-        floor.add_vent((8, 0), (0, 8));
-        floor.add_vent((6, 4), (2, 0));
-        floor.add_vent((0, 0), (8, 8));
-        assert_eq!(expected, floor)
+        floor.add_vent((0, 0), (0, 0)).unwrap();
+        floor.add_vent((5_000, 0), (5_000, 5_000)).unwrap();
+        assert!(floor.render_pgm().is_err());
+    }
+
+    #[test]
+    fn test_render_format_from_str() {
+        assert_eq!(Ok(RenderFormat::Ascii), RenderFormat::from_str("ascii"));
+        assert_eq!(Ok(RenderFormat::Pgm), RenderFormat::from_str("pgm"));
+        assert!(RenderFormat::from_str("png").is_err());
+    }
+
+    #[test]
+    fn test_render_check_size() {
+        let small = [((0, 9), (5, 9)), ((9, 4), (3, 4))];
+        assert_eq!(Ok(()), RenderFormat::Ascii.check_size(&small));
+        assert_eq!(Ok(()), RenderFormat::Pgm.check_size(&small));
+        assert_eq!(Ok(()), RenderFormat::Ascii.check_size(&[]));
+
+        // Text is limited to small maps, which include the origin.
+        let medium = [((2_000, 2_000), (2_000, 2_001))];
+        assert_eq!(
+            Err(String::from(
+                "Vent map (0, 0) -> (2000, 2001) is too large to render as Ascii"
+            )),
+            RenderFormat::Ascii.check_size(&medium)
+        );
+        assert_eq!(Ok(()), RenderFormat::Pgm.check_size(&medium));
+
+        let large = [
+            ((0, 0), (0, 0)),
+            ((900_000_000, 0), (900_000_000, 900_000_000)),
+        ];
+        assert!(RenderFormat::Ascii.check_size(&large).is_err());
+        assert!(RenderFormat::Pgm.check_size(&large).is_err());
+    }
+
+    #[test]
+    fn test_orientation() {
+        assert_eq!(Ok(Orientation::Horizontal), Orientation::of((0, 9), (5, 9)));
+        assert_eq!(Ok(Orientation::Vertical), Orientation::of((7, 0), (7, 4)));
+        assert_eq!(Ok(Orientation::Diagonal), Orientation::of((8, 0), (0, 8)));
+        assert_eq!(Ok(Orientation::Horizontal), Orientation::of((1, 1), (1, 1)));
+        assert!(Orientation::of((0, 0), (2, 1)).is_err());
+    }
+
+    #[test]
+    fn test_reject_skewed_segment() {
+        let input: Vec<String> = vec![String::from("0,0 -> 2,1")];
+        assert!(OceanFloor::from_filtered(&input[..], SegmentFilter::Straight).is_err());
+
+        let mut floor = OceanFloor::new();
+        assert!(floor.add_vent((0, 0), (2, 1)).is_err());
+        assert!(cells(&floor).is_empty());
+    }
+
+    #[test]
+    fn test_add_diagonal() {
+        let expected = HashMap::from([
+            ((0, 0), 1),
+            ((0, 8), 1),
+            ((1, 1), 1),
+            ((1, 7), 1),
+            ((2, 0), 1),
+            ((2, 2), 1),
+            ((2, 6), 1),
+            ((3, 1), 1),
+            ((3, 3), 1),
+            ((3, 5), 1),
+            ((4, 2), 1),
+            ((4, 4), 2),
+            ((5, 3), 2),
+            ((5, 5), 1),
+            ((6, 2), 1),
+            ((6, 4), 1),
+            ((6, 6), 1),
+            ((7, 1), 1),
+            ((7, 7), 1),
+            ((8, 0), 1),
+            ((8, 8), 1),
+        ]);
+
+        let mut floor = OceanFloor::new();
+        floor.add_vent((8, 0), (0, 8)).unwrap();
+        floor.add_vent((6, 4), (2, 0)).unwrap();
+        floor.add_vent((0, 0), (8, 8)).unwrap();
+        assert_eq!(expected, cells(&floor))
+    }
+
+    #[test]
+    fn test_dense_grid() {
+        let mut grid = DenseGrid::new((2, 3), (4, 4));
+        assert_eq!((2, 3), grid.cells.dim());
+        grid.add((2, 3));
+        grid.add((4, 4));
+        grid.add((4, 4));
+        assert_eq!(1, grid.count_overlaps());
+        assert_eq!(
+            HashMap::from([((2, 3), 1), ((4, 4), 2)]),
+            grid.iter().collect::<HashMap<(i32, i32), u32>>()
+        );
+    }
+
+    #[test]
+    fn test_dense_grid_fits() {
+        assert!(DenseGrid::fits((0, 0), (999, 999)));
+        assert!(!DenseGrid::fits((0, 0), (999_999, 999_999)));
+        assert!(!DenseGrid::fits((i32::MIN, i32::MIN), (i32::MAX, i32::MAX)));
+    }
+
+    #[test]
+    fn test_sparse_fallback() {
+        let input: Vec<String> = vec![
+            String::from("0,0 -> 0,2"),
+            String::from("0,1 -> 2,1"),
+            String::from("1000000,1000000 -> 1000000,1000001"),
+        ];
+
+        let floor = OceanFloor::from(&input[..]);
+        assert_eq!(1, floor.count_overlaps());
+        assert_eq!(Some(&1), cells(&floor).get(&(1000000, 1000001)));
     }
 }
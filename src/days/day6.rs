@@ -1,58 +1,620 @@
 use crate::utils::challenge::Challenge;
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+use std::fs;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+/// A number type the fish in each age bucket can be counted with.
+trait Counter: Clone + Display {
+    fn zero() -> Self;
+    fn one() -> Self;
+    fn from_count(n: usize) -> Option<Self>;
+    /// Returns `None` if the sum does not fit into the type.
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+    /// Returns `None` if the product does not fit into the type.
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+    /// Approximates the count, saturating at infinity.
+    fn to_f64(&self) -> f64;
+}
+
+macro_rules! impl_counter {
+    ($($t:ty),*) => {
+        $(impl Counter for $t {
+            fn zero() -> Self {
+                0
+            }
+
+            fn one() -> Self {
+                1
+            }
+
+            fn from_count(n: usize) -> Option<Self> {
+                <$t>::try_from(n).ok()
+            }
+
+            fn checked_add(&self, other: &Self) -> Option<Self> {
+                <$t>::checked_add(*self, *other)
+            }
+
+            fn checked_mul(&self, other: &Self) -> Option<Self> {
+                <$t>::checked_mul(*self, *other)
+            }
+
+            fn to_f64(&self) -> f64 {
+                *self as f64
+            }
+        })*
+    };
+}
+
+impl_counter!(u8, u16, u32, u64, u128, usize);
+
+impl Counter for BigUint {
+    fn zero() -> Self {
+        BigUint::from(0u32)
+    }
+
+    fn one() -> Self {
+        BigUint::from(1u32)
+    }
+
+    fn from_count(n: usize) -> Option<Self> {
+        Some(BigUint::from(n))
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(self + other)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        Some(self * other)
+    }
+
+    fn to_f64(&self) -> f64 {
+        ToPrimitive::to_f64(self).unwrap_or(f64::INFINITY)
+    }
+}
+
+/// The arithmetic the transition matrix is evaluated with.
+trait Semiring<T> {
+    fn zero(&self) -> T;
+    fn one(&self) -> T;
+    fn add(&self, a: &T, b: &T) -> Option<T>;
+    fn mul(&self, a: &T, b: &T) -> Option<T>;
+}
+
+/// Exact arithmetic that fails once a counter overflows.
+struct Checked;
+
+impl<T: Counter> Semiring<T> for Checked {
+    fn zero(&self) -> T {
+        T::zero()
+    }
+
+    fn one(&self) -> T {
+        T::one()
+    }
+
+    fn add(&self, a: &T, b: &T) -> Option<T> {
+        a.checked_add(b)
+    }
+
+    fn mul(&self, a: &T, b: &T) -> Option<T> {
+        a.checked_mul(b)
+    }
+}
+
+/// The largest modulus for which the sum of two residues fits a `u64`.
+const MAX_MODULUS: u64 = 1 << 63;
+
+/// Arithmetic modulo a number from 1 up to and including `MAX_MODULUS`, which
+/// never overflows.
+struct Modulo(u64);
+
+impl Semiring<u64> for Modulo {
+    fn zero(&self) -> u64 {
+        0
+    }
+
+    fn one(&self) -> u64 {
+        1 % self.0
+    }
+
+    fn add(&self, a: &u64, b: &u64) -> Option<u64> {
+        Some((a + b) % self.0)
+    }
+
+    fn mul(&self, a: &u64, b: &u64) -> Option<u64> {
+        Some((*a as u128 * *b as u128 % self.0 as u128) as u64)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Matrix<T>(Vec<Vec<T>>);
+
+impl<T: Clone> Matrix<T> {
+    fn identity<R: Semiring<T>>(size: usize, ring: &R) -> Self {
+        Self(
+            (0..size)
+                .map(|i| {
+                    (0..size)
+                        .map(|j| if i == j { ring.one() } else { ring.zero() })
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+
+    fn apply<R: Semiring<T>>(&self, v: &[T], ring: &R) -> Option<Vec<T>> {
+        self.0
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(v)
+                    .try_fold(ring.zero(), |acc, (a, b)| ring.add(&acc, &ring.mul(a, b)?))
+            })
+            .collect()
+    }
+
+    fn mul<R: Semiring<T>>(&self, other: &Self, ring: &R) -> Option<Self> {
+        let size = other.0.len();
+        let rows = self
+            .0
+            .iter()
+            .map(|row| {
+                (0..size)
+                    .map(|j| {
+                        row.iter()
+                            .zip(&other.0)
+                            .try_fold(ring.zero(), |acc, (a, r)| {
+                                ring.add(&acc, &ring.mul(a, &r[j])?)
+                            })
+                    })
+                    .collect()
+            })
+            .collect::<Option<Vec<Vec<T>>>>()?;
+
+        Some(Self(rows))
+    }
+
+    /// Raises the matrix to `exp` by repeated squaring.
+    fn pow<R: Semiring<T>>(&self, mut exp: u64, ring: &R) -> Option<Self> {
+        let mut result = Self::identity(self.0.len(), ring);
+        let mut base = self.clone();
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result.mul(&base, ring)?;
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = base.mul(&base, ring)?;
+            }
+        }
+
+        Some(result)
+    }
+}
+
+/// How lanternfish age and reproduce.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LifecycleConfig {
+    /// Days between two spawns of an adult fish.
+    pub spawn_interval: usize,
+    /// Extra days a newborn needs before its first spawn.
+    pub newborn_delay: usize,
+    /// Age in days at which a fish dies, if fish die at all.
+    pub death_age: Option<usize>,
+}
+
+impl Default for LifecycleConfig {
+    fn default() -> Self {
+        Self {
+            spawn_interval: 7,
+            newborn_delay: 2,
+            death_age: None,
+        }
+    }
+}
+
+/// The population is kept in buckets of fish that behave the same from now
+/// on. Immortal fish are bucketed by their timer, mortal fish by their age.
+impl LifecycleConfig {
+    fn validate(&self) -> Result<(), String> {
+        if self.spawn_interval == 0 {
+            return Err(String::from("The spawn interval must be at least one day"));
+        }
+        if self.death_age == Some(0) {
+            return Err(String::from("The death age must be at least one day"));
+        }
+
+        Ok(())
+    }
+
+    /// Timer of a newborn fish.
+    fn newborn_timer(&self) -> usize {
+        self.spawn_interval + self.newborn_delay - 1
+    }
+
+    fn buckets(&self) -> usize {
+        self.death_age.unwrap_or(self.newborn_timer() + 1)
+    }
+
+    fn newborn(&self) -> usize {
+        match self.death_age {
+            Some(_) => 0,
+            None => self.newborn_timer(),
+        }
+    }
+
+    /// Returns the bucket a fish with timer `timer` belongs to, assuming it
+    /// has not spawned yet, or `None` if such a fish would be dead already.
+    fn bucket_of_timer(&self, timer: usize) -> Result<Option<usize>, String> {
+        if timer > self.newborn_timer() {
+            return Err(format!(
+                "Timer {} exceeds the newborn timer {}",
+                timer,
+                self.newborn_timer()
+            ));
+        }
+
+        Ok(match self.death_age {
+            Some(death) => Some(self.newborn_timer() - timer).filter(|age| *age < death),
+            None => Some(timer),
+        })
+    }
+
+    /// Returns the bucket the fish of `bucket` move to on the next day, or
+    /// `None` if they die.
+    fn next(&self, bucket: usize) -> Option<usize> {
+        match self.death_age {
+            Some(death) => Some(bucket + 1).filter(|age| *age < death),
+            None if bucket == 0 => Some(self.spawn_interval - 1),
+            None => Some(bucket - 1),
+        }
+    }
+
+    fn spawns(&self, bucket: usize) -> bool {
+        let first = self.newborn_timer();
+        match self.death_age {
+            Some(_) => bucket >= first && (bucket - first).is_multiple_of(self.spawn_interval),
+            None => bucket == 0,
+        }
+    }
+}
 
 #[derive(Debug, PartialEq)]
-struct LanternfishPopulation {
-    ages: Vec<usize>,
+struct LanternfishPopulation<T = usize> {
+    ages: Vec<T>,
+    config: LifecycleConfig,
 }
 
-impl From<&str> for LanternfishPopulation {
+impl<T: Counter> From<&str> for LanternfishPopulation<T> {
     fn from(input: &str) -> Self {
-        let mut ages: Vec<usize> = vec![0, 0, 0, 0, 0, 0, 0, 0, 0];
-        let timers: Vec<u32> = input
-            .split(',')
-            .map(|s| s.parse::<u32>().unwrap())
-            .collect();
+        Self::parse(input, LifecycleConfig::default()).unwrap()
+    }
+}
+
+impl<T: Counter> LanternfishPopulation<T> {
+    fn parse(input: &str, config: LifecycleConfig) -> Result<Self, String> {
+        config.validate()?;
+
+        let mut counts = vec![0; config.buckets()];
+        for s in input.split(',') {
+            let timer = s
+                .trim()
+                .parse::<usize>()
+                .map_err(|e| format!("Invalid timer {:?}: {}", s, e))?;
+            if let Some(bucket) = config.bucket_of_timer(timer)? {
+                counts[bucket] += 1;
+            }
+        }
+
+        let ages = counts
+            .into_iter()
+            .map(|count| T::from_count(count).ok_or("Too many fish for the counter type"))
+            .collect::<Result<Vec<T>, &str>>()?;
+
+        Ok(Self { ages, config })
+    }
+
+    fn overflow(days: usize) -> String {
+        format!(
+            "Population overflows {} after {} days",
+            std::any::type_name::<T>(),
+            days
+        )
+    }
+
+    fn simulate_day(&mut self) -> Option<()> {
+        let mut ages = vec![T::zero(); self.ages.len()];
+        let newborn = self.config.newborn();
+        for (bucket, count) in self.ages.iter().enumerate() {
+            if let Some(next) = self.config.next(bucket) {
+                ages[next] = ages[next].checked_add(count)?;
+            }
+            if self.config.spawns(bucket) {
+                ages[newborn] = ages[newborn].checked_add(count)?;
+            }
+        }
+        self.ages = ages;
+
+        Some(())
+    }
+
+    fn count(&self) -> Option<T> {
+        self.ages
+            .iter()
+            .try_fold(T::zero(), |acc, count| acc.checked_add(count))
+    }
+
+    fn simulate(&mut self, days: usize) -> Result<T, String> {
+        for day in 1..=days {
+            self.simulate_day().ok_or_else(|| Self::overflow(day))?;
+        }
+
+        self.count().ok_or_else(|| Self::overflow(days))
+    }
+
+    /// Simulates `days` like `simulate`, keeping the buckets of every day.
+    fn record(&mut self, days: usize) -> Result<History<T>, String> {
+        let mut history = History {
+            buckets: vec![self.ages.clone()],
+        };
+        for day in 1..=days {
+            self.simulate_day().ok_or_else(|| Self::overflow(day))?;
+            self.count().ok_or_else(|| Self::overflow(day))?;
+            history.buckets.push(self.ages.clone());
+        }
 
-        for (i, v) in ages.iter_mut().enumerate() {
-            *v = timers.iter().filter(|&v| *v == i as u32).count();
+        Ok(history)
+    }
+
+    /// The matrix that advances the buckets by one day.
+    fn transition<R: Semiring<T>>(&self, ring: &R) -> Option<Matrix<T>> {
+        let size = self.ages.len();
+        let mut m = Matrix(vec![vec![ring.zero(); size]; size]);
+        let newborn = self.config.newborn();
+        for bucket in 0..size {
+            if let Some(next) = self.config.next(bucket) {
+                m.0[next][bucket] = ring.add(&m.0[next][bucket], &ring.one())?;
+            }
+            if self.config.spawns(bucket) {
+                m.0[newborn][bucket] = ring.add(&m.0[newborn][bucket], &ring.one())?;
+            }
         }
 
-        Self { ages }
+        Some(m)
+    }
+
+    fn fast_forward_with<R: Semiring<T>>(&mut self, days: u64, ring: &R) -> Option<T> {
+        let ages = self
+            .transition(ring)?
+            .pow(days, ring)?
+            .apply(&self.ages, ring)?;
+        let count = ages
+            .iter()
+            .try_fold(ring.zero(), |acc, count| ring.add(&acc, count))?;
+        self.ages = ages;
+
+        Some(count)
+    }
+
+    /// Advances the population by `days` in `O(log days)` matrix products.
+    fn fast_forward(&mut self, days: u64) -> Result<T, String> {
+        self.fast_forward_with(days, &Checked)
+            .ok_or_else(|| Self::overflow(days as usize))
     }
 }
 
-impl LanternfishPopulation {
-    fn simulate_day(&mut self) {
-        self.ages = vec![
-            self.ages[1],
-            self.ages[2],
-            self.ages[3],
-            self.ages[4],
-            self.ages[5],
-            self.ages[6],
-            self.ages[7] + self.ages[0],
-            self.ages[8],
-            self.ages[0],
-        ];
+impl LanternfishPopulation<u64> {
+    /// Counts the fish after `days` modulo `modulus`, which works for
+    /// horizons whose exact count would not fit into memory.
+    fn count_modulo(&self, days: u64, modulus: u64) -> u64 {
+        let ring = Modulo(modulus);
+        let mut pop = Self {
+            ages: self.ages.iter().map(|a| a % modulus).collect(),
+            config: self.config,
+        };
+
+        pop.fast_forward_with(days, &ring).unwrap()
     }
+}
+
+/// The buckets of a population for every simulated day, starting with the
+/// initial state.
+#[derive(Debug, PartialEq)]
+struct History<T> {
+    buckets: Vec<Vec<T>>,
+}
 
-    fn simulate(&mut self, days: usize) -> usize {
-        for _ in 0..days {
-            self.simulate_day();
+/// Day over day growth of the total population.
+#[derive(Debug, PartialEq)]
+struct GrowthStats {
+    mean: f64,
+    min: f64,
+    max: f64,
+    last: f64,
+}
+
+impl Display for GrowthStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Daily growth: mean {:.4}, min {:.4}, max {:.4}, last {:.4}",
+            self.mean, self.min, self.max, self.last
+        )
+    }
+}
+
+impl<T: Counter> History<T> {
+    fn totals(&self) -> Vec<T> {
+        self.buckets
+            .iter()
+            .map(|day| {
+                day.iter()
+                    .try_fold(T::zero(), |acc, count| acc.checked_add(count))
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    /// Returns the ratio of the total population to the day before for every
+    /// day with a living population the day before.
+    fn growth_rates(&self) -> Vec<f64> {
+        self.totals()
+            .windows(2)
+            .filter(|w| w[0].to_f64() > 0.0)
+            .map(|w| w[1].to_f64() / w[0].to_f64())
+            .collect()
+    }
+
+    fn growth_stats(&self) -> Option<GrowthStats> {
+        let rates = self.growth_rates();
+        let last = *rates.last()?;
+        // The mean rate is the geometric one, so it reproduces the total
+        // growth over the whole horizon.
+        let mean = rates.iter().map(|r| r.ln()).sum::<f64>() / rates.len() as f64;
+
+        Some(GrowthStats {
+            mean: mean.exp(),
+            min: rates.iter().cloned().fold(f64::INFINITY, f64::min),
+            max: rates.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+            last,
+        })
+    }
+
+    fn to_csv(&self) -> String {
+        let size = self.buckets.first().map_or(0, |b| b.len());
+        let header: Vec<String> = (0..size).map(|i| i.to_string()).collect();
+        let mut csv = format!("day,{},total\n", header.join(","));
+
+        for (day, (buckets, total)) in self.buckets.iter().zip(self.totals()).enumerate() {
+            let counts: Vec<String> = buckets.iter().map(|c| c.to_string()).collect();
+            csv += &format!("{},{},{}\n", day, counts.join(","), total);
+        }
+
+        csv
+    }
+
+    fn to_json(&self) -> String {
+        let days: Vec<String> = self
+            .buckets
+            .iter()
+            .zip(self.totals())
+            .enumerate()
+            .map(|(day, (buckets, total))| {
+                let counts: Vec<String> = buckets.iter().map(|c| c.to_string()).collect();
+                format!(
+                    "{{\"day\":{},\"buckets\":[{}],\"total\":{}}}",
+                    day,
+                    counts.join(","),
+                    total
+                )
+            })
+            .collect();
+
+        format!("[{}]\n", days.join(","))
+    }
+
+    fn export(&self, format: HistoryFormat) -> String {
+        match format {
+            HistoryFormat::Csv => self.to_csv(),
+            HistoryFormat::Json => self.to_json(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HistoryFormat {
+    Csv,
+    Json,
+}
+
+impl FromStr for HistoryFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            x => Err(format!("Unknown history format: {}", x)),
         }
+    }
+}
+
+/// Exports the history of `days` and returns its growth statistics, falling
+/// back to arbitrary precision if native integers overflow.
+fn export_history(
+    input: &str,
+    days: usize,
+    config: LifecycleConfig,
+    format: HistoryFormat,
+) -> Result<(String, Option<GrowthStats>), String> {
+    fn export<T: Counter>(
+        input: &str,
+        days: usize,
+        config: LifecycleConfig,
+        format: HistoryFormat,
+    ) -> Result<(String, Option<GrowthStats>), String> {
+        let history = LanternfishPopulation::<T>::parse(input, config)?.record(days)?;
+        Ok((history.export(format), history.growth_stats()))
+    }
+
+    export::<u64>(input, days, config, format)
+        .or_else(|_| export::<BigUint>(input, days, config, format))
+}
+
+/// Horizon up to which stepping day by day is cheaper than the matrix products.
+const STEP_LIMIT: u64 = 4096;
+
+fn advance<T: Counter>(input: &str, days: u64, config: LifecycleConfig) -> Result<T, String> {
+    let mut pop = LanternfishPopulation::<T>::parse(input, config)?;
+    if days <= STEP_LIMIT {
+        pop.simulate(days as usize)
+    } else {
+        pop.fast_forward(days)
+    }
+}
+
+/// Horizon up to which exact counts stay quick. The count has a number of
+/// digits linear in the days, so beyond it only counts modulo a number are
+/// feasible.
+const EXACT_LIMIT: u64 = 1 << 20;
 
-        self.ages.iter().sum()
+/// Counts the fish after `days` with native integers, falling back to
+/// arbitrary precision once those overflow.
+fn count_fish(input: &str, days: u64, config: LifecycleConfig) -> Result<String, String> {
+    match advance::<u64>(input, days, config) {
+        Ok(fishes) => Ok(fishes.to_string()),
+        Err(_) if days > EXACT_LIMIT => Err(format!(
+            "The exact count after {} days is too large, pass --modulo for horizons above {} days",
+            days, EXACT_LIMIT
+        )),
+        Err(_) => advance::<BigUint>(input, days, config).map(|f| f.to_string()),
     }
 }
 
 pub struct Day6 {
     data: Vec<String>,
+    days: Option<u64>,
+    modulo: Option<u64>,
+    config: LifecycleConfig,
+    history: Option<HistoryFormat>,
+    history_to: Option<String>,
 }
 
-impl Challenge <String>for Day6 {
+impl Challenge<String> for Day6 {
     fn new(input_file: &str) -> Self {
         Self {
             data: Self::load(input_file).unwrap(),
+            days: None,
+            modulo: None,
+            config: LifecycleConfig::default(),
+            history: None,
+            history_to: None,
         }
     }
 
@@ -70,16 +632,91 @@ impl Challenge <String>for Day6 {
 }
 
 impl Day6 {
+    /// Overrides the number of days implied by the part.
+    pub fn with_days(mut self, days: Option<u64>) -> Self {
+        self.days = days;
+        self
+    }
+
+    /// Reports the count modulo `modulo` instead of the exact count.
+    pub fn with_modulo(mut self, modulo: Option<u64>) -> Self {
+        self.modulo = modulo;
+        self
+    }
+
+    pub fn with_lifecycle(mut self, config: LifecycleConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Exports the population of every day to `history_to`, or to stdout if no
+    /// file is given.
+    pub fn with_history(
+        mut self,
+        history: Option<HistoryFormat>,
+        history_to: Option<String>,
+    ) -> Self {
+        self.history = history;
+        self.history_to = history_to;
+        self
+    }
+
+    /// Writes only the export to `out` and returns the growth statistics, so
+    /// the export stays machine readable.
+    fn export_history_to(
+        &self,
+        out: &mut dyn Write,
+        days: u64,
+        format: HistoryFormat,
+    ) -> Result<Option<GrowthStats>, String> {
+        let days = usize::try_from(days).map_err(|e| format!("Invalid days: {}", e))?;
+        let (export, stats) = export_history(&self.data[0], days, self.config, format)?;
+        out.write_all(export.as_bytes())
+            .map_err(|e| format!("Error while exporting the history: {}", e))?;
+
+        Ok(stats)
+    }
+
+    fn write_history(&self, days: u64, format: HistoryFormat) -> Result<(), String> {
+        let stats = match &self.history_to {
+            Some(path) => {
+                let mut file = fs::File::create(path)
+                    .map_err(|e| format!("Error while exporting the history: {}", e))?;
+                self.export_history_to(&mut file, days, format)?
+            }
+            None => self.export_history_to(&mut io::stdout(), days, format)?,
+        };
+
+        // The statistics go to stderr, the export may be piped into a plotter.
+        if let Some(stats) = stats {
+            eprintln!("{}", stats);
+        }
+
+        Ok(())
+    }
+
+    fn solve(&self, days: u64) -> Result<String, String> {
+        if let Some(format) = self.history {
+            self.write_history(days, format)?;
+        }
+
+        match self.modulo {
+            Some(m) if m == 0 || m > MAX_MODULUS => Err(format!("Invalid modulus: {}", m)),
+            Some(m) => Ok(
+                LanternfishPopulation::<u64>::parse(&self.data[0], self.config)?
+                    .count_modulo(days, m)
+                    .to_string(),
+            ),
+            None => count_fish(&self.data[0], days, self.config),
+        }
+    }
+
     fn run_part_one(&self) -> Result<String, String> {
-        let mut pop = LanternfishPopulation::from(&self.data[0][..]);
-        let fishes = pop.simulate(80);
-        Ok(format!("{:#?}", fishes))
+        self.solve(self.days.unwrap_or(80))
     }
 
     fn run_part_two(&self) -> Result<String, String> {
-        let mut pop = LanternfishPopulation::from(&self.data[0][..]);
-        let fishes = pop.simulate(256);
-        Ok(format!("{:#?}", fishes))
+        self.solve(self.days.unwrap_or(256))
     }
 }
 
@@ -91,8 +728,9 @@ mod tests {
     fn test_from() {
         let expected = LanternfishPopulation {
             ages: vec![0, 1, 1, 2, 1, 0, 0, 0, 0],
+            config: LifecycleConfig::default(),
         };
-        let pop = LanternfishPopulation::from("3,4,3,1,2");
+        let pop = LanternfishPopulation::<usize>::from("3,4,3,1,2");
         assert_eq!(expected, pop);
     }
 
@@ -100,8 +738,9 @@ mod tests {
     fn test_simulate_one_day() {
         let expected = LanternfishPopulation {
             ages: vec![1, 1, 2, 1, 0, 0, 0, 0, 0],
+            config: LifecycleConfig::default(),
         };
-        let mut pop = LanternfishPopulation::from("3,4,3,1,2");
+        let mut pop = LanternfishPopulation::<usize>::from("3,4,3,1,2");
         pop.simulate_day();
         assert_eq!(expected, pop);
     }
@@ -110,8 +749,9 @@ mod tests {
     fn test_simulate_two_days() {
         let expected = LanternfishPopulation {
             ages: vec![1, 2, 1, 0, 0, 0, 1, 0, 1],
+            config: LifecycleConfig::default(),
         };
-        let mut pop = LanternfishPopulation::from("3,4,3,1,2");
+        let mut pop = LanternfishPopulation::<usize>::from("3,4,3,1,2");
         pop.simulate_day();
         pop.simulate_day();
         assert_eq!(expected, pop);
@@ -119,10 +759,275 @@ mod tests {
 
     #[test]
     fn test_simulate() {
-        let mut pop = LanternfishPopulation::from("3,4,3,1,2");
+        let mut pop = LanternfishPopulation::<usize>::from("3,4,3,1,2");
         let fishes = pop.simulate(18);
-        assert_eq!(26, fishes);
+        assert_eq!(Ok(26), fishes);
         let fishes = pop.simulate(80 - 18);
-        assert_eq!(5934, fishes);
+        assert_eq!(Ok(5934), fishes);
+    }
+
+    #[test]
+    fn test_simulate_overflow() {
+        let mut pop = LanternfishPopulation::<u8>::from("3,4,3,1,2");
+        assert!(pop.simulate(18).is_ok());
+        let mut pop = LanternfishPopulation::<u8>::from("3,4,3,1,2");
+        assert!(pop.simulate(80).is_err());
+
+        let mut pop = LanternfishPopulation::<u64>::from("3,4,3,1,2");
+        assert!(pop.simulate(489).is_ok());
+        let mut pop = LanternfishPopulation::<u64>::from("3,4,3,1,2");
+        assert_eq!(
+            Err(String::from("Population overflows u64 after 490 days")),
+            pop.simulate(490)
+        );
+    }
+
+    #[test]
+    fn test_simulate_big() {
+        let mut native = LanternfishPopulation::<u128>::from("3,4,3,1,2");
+        let mut big = LanternfishPopulation::<BigUint>::from("3,4,3,1,2");
+        assert_eq!(
+            native.simulate(900).unwrap().to_string(),
+            big.simulate(900).unwrap().to_string()
+        );
+
+        assert_eq!(
+            "379589061144698259131825683795505058481",
+            count_fish("3,4,3,1,2", 1000, LifecycleConfig::default()).unwrap()
+        );
+        assert_eq!(
+            "26984457539",
+            count_fish("3,4,3,1,2", 256, LifecycleConfig::default()).unwrap()
+        );
+        assert_eq!(
+            count_fish("3,4,3,1,2", STEP_LIMIT, LifecycleConfig::default()).unwrap(),
+            LanternfishPopulation::<BigUint>::from("3,4,3,1,2")
+                .fast_forward(STEP_LIMIT)
+                .unwrap()
+                .to_string()
+        );
+        assert!(count_fish("3,4,3,1,2", 1_000_000_000_000, LifecycleConfig::default()).is_err());
+
+        // Fish dying before they spawn never overflow, whatever the horizon.
+        let config = LifecycleConfig {
+            death_age: Some(1),
+            ..LifecycleConfig::default()
+        };
+        assert_eq!(
+            Ok(String::from("0")),
+            count_fish("8", 1_000_000_000_000, config)
+        );
+    }
+
+    #[test]
+    fn test_fast_forward() {
+        for days in 0..300 {
+            let mut step = LanternfishPopulation::<u128>::from("3,4,3,1,2");
+            let mut fast = LanternfishPopulation::<u128>::from("3,4,3,1,2");
+            assert_eq!(step.simulate(days), fast.fast_forward(days as u64));
+            assert_eq!(step, fast);
+        }
+
+        let mut step = LanternfishPopulation::<BigUint>::from("3,4,3,1,2");
+        let mut fast = LanternfishPopulation::<BigUint>::from("3,4,3,1,2");
+        assert_eq!(step.simulate(2000), fast.fast_forward(2000));
+    }
+
+    #[test]
+    fn test_fast_forward_overflow() {
+        let mut pop = LanternfishPopulation::<u64>::from("3,4,3,1,2");
+        assert!(pop.fast_forward(489).is_ok());
+        let mut pop = LanternfishPopulation::<u64>::from("3,4,3,1,2");
+        assert!(pop.fast_forward(490).is_err());
+    }
+
+    #[test]
+    fn test_lifecycle_parse() {
+        let config = LifecycleConfig {
+            spawn_interval: 3,
+            newborn_delay: 1,
+            death_age: None,
+        };
+        let pop = LanternfishPopulation::<usize>::parse("3,1,2", config).unwrap();
+        assert_eq!(vec![0, 1, 1, 1], pop.ages);
+        assert!(LanternfishPopulation::<usize>::parse("4", config).is_err());
+        assert!(LanternfishPopulation::<usize>::parse("x", config).is_err());
+
+        // Mortal fish are bucketed by age, a timer of 8 is a newborn.
+        let config = LifecycleConfig {
+            death_age: Some(7),
+            ..LifecycleConfig::default()
+        };
+        let pop = LanternfishPopulation::<usize>::parse("8,3,1", config).unwrap();
+        assert_eq!(vec![1, 0, 0, 0, 0, 1, 0], pop.ages);
+
+        let config = LifecycleConfig {
+            spawn_interval: 0,
+            ..LifecycleConfig::default()
+        };
+        assert!(LanternfishPopulation::<usize>::parse("3", config).is_err());
+    }
+
+    #[test]
+    fn test_lifecycle_mortal_matches_immortal() {
+        // Fish that die long after the horizon behave like immortal ones.
+        let config = LifecycleConfig {
+            death_age: Some(200),
+            ..LifecycleConfig::default()
+        };
+        let mut mortal = LanternfishPopulation::<u64>::parse("3,4,3,1,2", config).unwrap();
+        assert_eq!(Ok(5934), mortal.simulate(80));
+    }
+
+    #[test]
+    fn test_lifecycle_death() {
+        // A newborn spawns on day 9 and day 16, then dies on day 20.
+        let config = LifecycleConfig {
+            death_age: Some(20),
+            ..LifecycleConfig::default()
+        };
+        let counts: Vec<u64> = (0..=20)
+            .map(|days| {
+                let mut pop = LanternfishPopulation::<u64>::parse("8", config).unwrap();
+                pop.simulate(days).unwrap()
+            })
+            .collect();
+        assert_eq!(1, counts[8]);
+        assert_eq!(2, counts[9]);
+        assert_eq!(3, counts[16]);
+        assert_eq!(3, counts[17]);
+        // The first child spawns on day 18.
+        assert_eq!(4, counts[18]);
+        assert_eq!(4, counts[19]);
+        assert_eq!(3, counts[20]);
+    }
+
+    #[test]
+    fn test_lifecycle_fast_forward() {
+        let configs = [
+            LifecycleConfig {
+                spawn_interval: 5,
+                newborn_delay: 0,
+                death_age: None,
+            },
+            LifecycleConfig {
+                spawn_interval: 4,
+                newborn_delay: 3,
+                death_age: Some(30),
+            },
+        ];
+        for config in configs {
+            for days in [0, 1, 17, 100] {
+                let mut step = LanternfishPopulation::<u128>::parse("3,4,3,1,2", config).unwrap();
+                let mut fast = LanternfishPopulation::<u128>::parse("3,4,3,1,2", config).unwrap();
+                assert_eq!(step.simulate(days), fast.fast_forward(days as u64));
+            }
+        }
+    }
+
+    #[test]
+    fn test_record() {
+        let mut pop = LanternfishPopulation::<usize>::from("3,4,3,1,2");
+        let history = pop.record(18).unwrap();
+        assert_eq!(19, history.buckets.len());
+        assert_eq!(vec![0, 1, 1, 2, 1, 0, 0, 0, 0], history.buckets[0]);
+        assert_eq!(vec![1, 2, 1, 0, 0, 0, 1, 0, 1], history.buckets[2]);
+        assert_eq!(Some(&26), history.totals().last());
+        assert_eq!(history.buckets[18], pop.ages);
+
+        let mut pop = LanternfishPopulation::<u8>::from("3,4,3,1,2");
+        assert!(pop.record(80).is_err());
+    }
+
+    #[test]
+    fn test_history_export() {
+        let mut pop = LanternfishPopulation::<usize>::from("3,4,3,1,2");
+        let history = pop.record(2).unwrap();
+        assert_eq!(
+            "day,0,1,2,3,4,5,6,7,8,total\n\
+             0,0,1,1,2,1,0,0,0,0,5\n\
+             1,1,1,2,1,0,0,0,0,0,5\n\
+             2,1,2,1,0,0,0,1,0,1,6\n",
+            history.export(HistoryFormat::Csv)
+        );
+        assert_eq!(
+            "[{\"day\":0,\"buckets\":[0,1,1,2,1,0,0,0,0],\"total\":5},\
+             {\"day\":1,\"buckets\":[1,1,2,1,0,0,0,0,0],\"total\":5},\
+             {\"day\":2,\"buckets\":[1,2,1,0,0,0,1,0,1],\"total\":6}]\n",
+            history.export(HistoryFormat::Json)
+        );
+    }
+
+    #[test]
+    fn test_export_history_to() {
+        let day = Day6 {
+            data: vec![String::from("3,4,3,1,2")],
+            days: None,
+            modulo: None,
+            config: LifecycleConfig::default(),
+            history: None,
+            history_to: None,
+        };
+        let mut pop = LanternfishPopulation::<u64>::from("3,4,3,1,2");
+        let history = pop.record(2).unwrap();
+
+        // The statistics are returned, never mixed into the export.
+        for format in [HistoryFormat::Csv, HistoryFormat::Json].iter() {
+            let mut out = Vec::new();
+            let stats = day.export_history_to(&mut out, 2, *format).unwrap();
+            assert_eq!(history.export(*format).into_bytes(), out);
+            assert_eq!(history.growth_stats(), stats);
+        }
+    }
+
+    #[test]
+    fn test_growth_stats() {
+        let mut pop = LanternfishPopulation::<usize>::from("3,4,3,1,2");
+        let history = pop.record(2).unwrap();
+        assert_eq!(vec![1.0, 1.2], history.growth_rates());
+
+        let stats = history.growth_stats().unwrap();
+        assert!((stats.mean - 1.2f64.sqrt()).abs() < 1e-12);
+        assert_eq!(1.0, stats.min);
+        assert_eq!(1.2, stats.max);
+        assert_eq!(1.2, stats.last);
+
+        // The population approaches the dominant eigenvalue of the transition.
+        let mut pop = LanternfishPopulation::<u128>::from("3,4,3,1,2");
+        let stats = pop.record(900).unwrap().growth_stats().unwrap();
+        assert!((stats.last - 1.0910).abs() < 1e-4);
+
+        let mut pop = LanternfishPopulation::<u64>::from("3,4,3,1,2");
+        assert_eq!(None, pop.record(0).unwrap().growth_stats());
+    }
+
+    #[test]
+    fn test_history_format_from_str() {
+        assert_eq!(Ok(HistoryFormat::Csv), HistoryFormat::from_str("csv"));
+        assert_eq!(Ok(HistoryFormat::Json), HistoryFormat::from_str("json"));
+        assert!(HistoryFormat::from_str("xml").is_err());
+    }
+
+    #[test]
+    fn test_count_modulo() {
+        let pop = LanternfishPopulation::<u64>::from("3,4,3,1,2");
+        let mut exact = LanternfishPopulation::<BigUint>::from("3,4,3,1,2");
+        let exact = exact.simulate(1000).unwrap();
+        assert_eq!(
+            (&exact % BigUint::from(1_000_000_007u64)).to_string(),
+            pop.count_modulo(1000, 1_000_000_007).to_string()
+        );
+
+        assert_eq!(
+            995077479,
+            pop.count_modulo(1_000_000_000_000, 1_000_000_007)
+        );
+        assert_eq!(0, pop.count_modulo(80, 1));
+
+        // The largest modulus still adds residues without overflowing.
+        assert_eq!(
+            (&exact % BigUint::from(MAX_MODULUS)).to_string(),
+            pop.count_modulo(1000, MAX_MODULUS).to_string()
+        );
     }
 }
@@ -1,47 +1,253 @@
 use crate::utils::challenge::Challenge;
 use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::str::FromStr;
 
 pub struct Day7 {
     data: Vec<String>,
+    fuel: Option<FuelModel>,
+    curve: bool,
+    curve_to: Option<String>,
 }
 
+/// Fuel a crab burns to move a distance.
+trait FuelCost {
+    fn cost(&self, dist: isize) -> isize;
+
+    /// Whether the total fuel has a single valley, so that all optimal
+    /// positions lie next to each other.
+    fn is_convex(&self) -> bool {
+        false
+    }
+
+    /// Returns the lowest position with the least fuel and that fuel. Checks
+    /// every position unless a cost knows better.
+    fn optimise(&self, crabs: &CrabMap) -> (isize, isize) {
+        crabs.brute_force(self)
+    }
+}
+
+/// One unit of fuel per step.
+struct Linear;
+
+impl FuelCost for Linear {
+    fn cost(&self, dist: isize) -> isize {
+        dist
+    }
+
+    fn is_convex(&self) -> bool {
+        true
+    }
+
+    fn optimise(&self, crabs: &CrabMap) -> (isize, isize) {
+        crabs.median_optimise()
+    }
+}
+
+/// Every step costs one more unit of fuel than the previous one.
+struct Triangular;
+
+impl FuelCost for Triangular {
+    fn cost(&self, dist: isize) -> isize {
+        dist * (dist + 1) / 2
+    }
+
+    fn is_convex(&self) -> bool {
+        true
+    }
+
+    fn optimise(&self, crabs: &CrabMap) -> (isize, isize) {
+        crabs.mean_optimise()
+    }
+}
+
+/// Fuel grows with the square of the distance.
+struct Quadratic;
+
+impl FuelCost for Quadratic {
+    fn cost(&self, dist: isize) -> isize {
+        dist * dist
+    }
+
+    fn is_convex(&self) -> bool {
+        true
+    }
+
+    fn optimise(&self, crabs: &CrabMap) -> (isize, isize) {
+        crabs.convex_optimise(self)
+    }
+}
+
+/// Fuel per step taken from a table, the last entry applies to all further
+/// steps. Weights can't be negative, so the cost never falls with distance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeightTable {
+    /// Fuel for the first `i` steps.
+    prefix: Vec<isize>,
+}
+
+impl WeightTable {
+    fn new(weights: &[isize]) -> Result<Self, String> {
+        if weights.is_empty() {
+            return Err(String::from("The weight table is empty"));
+        }
+        if let Some(w) = weights.iter().find(|w| **w < 0) {
+            return Err(format!("Negative weight {} in the weight table", w));
+        }
+
+        let mut prefix = vec![0];
+        for w in weights {
+            prefix.push(prefix.last().unwrap() + w);
+        }
+
+        Ok(Self { prefix })
+    }
+}
+
+impl FuelCost for WeightTable {
+    fn cost(&self, dist: isize) -> isize {
+        let steps = self.prefix.len() as isize - 1;
+        if dist <= steps {
+            self.prefix[dist as usize]
+        } else {
+            let last = self.prefix[steps as usize] - self.prefix[steps as usize - 1];
+            self.prefix[steps as usize] + (dist - steps) * last
+        }
+    }
+
+    fn is_convex(&self) -> bool {
+        self.prefix.windows(3).all(|w| w[2] - w[1] >= w[1] - w[0])
+    }
+
+    fn optimise(&self, crabs: &CrabMap) -> (isize, isize) {
+        if self.is_convex() {
+            crabs.convex_optimise(self)
+        } else {
+            crabs.brute_force(self)
+        }
+    }
+}
+
+/// The fuel cost selectable from the command line.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FuelModel {
+    Linear,
+    Triangular,
+    Quadratic,
+    Table(WeightTable),
+}
+
+impl FromStr for FuelModel {
+    type Err = String;
+
+    /// Parses `linear`, `triangular`, `quadratic` or a table of fuel per step
+    /// like `table:1,2,4`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "linear" => Ok(Self::Linear),
+            "triangular" => Ok(Self::Triangular),
+            "quadratic" => Ok(Self::Quadratic),
+            x if x.starts_with("table:") => {
+                let weights = x["table:".len()..]
+                    .split(',')
+                    .map(|w| w.parse::<isize>())
+                    .collect::<Result<Vec<isize>, _>>()
+                    .map_err(|e| format!("Invalid weight table {:?}: {}", x, e))?;
+                Ok(Self::Table(WeightTable::new(&weights)?))
+            }
+            x => Err(format!("Unknown fuel model: {}", x)),
+        }
+    }
+}
+
+impl FuelModel {
+    fn cost(&self) -> &dyn FuelCost {
+        match self {
+            Self::Linear => &Linear,
+            Self::Triangular => &Triangular,
+            Self::Quadratic => &Quadratic,
+            Self::Table(table) => table,
+        }
+    }
+}
+
+/// Where the crabs line up best and how much fuel it takes.
 #[derive(Debug, PartialEq)]
-struct CrabMap {
-    positions: HashMap<isize, usize>,
+struct Alignment {
+    /// All positions tied at the least fuel, in ascending order.
+    positions: Vec<isize>,
+    fuel: isize,
 }
 
-impl From<&str> for CrabMap {
-    fn from(input: &str) -> Self {
-        let crabs: Vec<isize> = input
-            .split(',')
-            .map(|s| s.parse::<isize>().unwrap())
+impl From<&[(isize, isize)]> for Alignment {
+    /// Picks the optimal positions from a cost curve.
+    fn from(curve: &[(isize, isize)]) -> Self {
+        let fuel = curve.iter().map(|(_, f)| *f).min().unwrap();
+        let positions = curve
+            .iter()
+            .filter(|(_, f)| *f == fuel)
+            .map(|(p, _)| *p)
             .collect();
 
-        let mut positions = HashMap::<isize, usize>::new();
-        for pos in crabs {
-            *positions.entry(pos).or_insert(0) += 1;
-        }
+        Self { positions, fuel }
+    }
+}
 
-        Self { positions }
+impl fmt::Display for Alignment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let positions: Vec<String> = self.positions.iter().map(|p| p.to_string()).collect();
+        write!(
+            f,
+            "Align at {} with {} fuel",
+            positions.join(" or "),
+            self.fuel
+        )
     }
 }
 
+/// Formats a cost curve as CSV for plotting.
+fn curve_to_csv(curve: &[(isize, isize)]) -> String {
+    let mut csv = String::from("position,fuel\n");
+    for (pos, fuel) in curve {
+        csv += &format!("{},{}\n", pos, fuel);
+    }
+    csv
+}
+
+/// Crabs by position, each counted with its weight.
+#[derive(Debug, PartialEq)]
+struct CrabMap {
+    positions: HashMap<isize, usize>,
+}
+
 impl CrabMap {
-    fn align_to(&self, pos: isize) -> isize {
-        self.positions
-            .iter()
-            .map(|(p, c)| isize::abs((pos - p) * *c as isize))
-            .sum::<isize>()
+    /// Parses crab positions, each optionally followed by a positive weight
+    /// like `4:3`.
+    fn parse(input: &str) -> Result<Self, String> {
+        let mut positions = HashMap::<isize, usize>::new();
+        for crab in input.trim_end().split(',') {
+            let (pos, weight) = crab.split_once(':').unwrap_or((crab, "1"));
+            let pos = pos
+                .parse::<isize>()
+                .map_err(|e| format!("Invalid crab position {:?}: {}", crab, e))?;
+            let weight = weight
+                .parse::<usize>()
+                .map_err(|e| format!("Invalid crab weight {:?}: {}", crab, e))?;
+            if weight == 0 {
+                return Err(format!("Crab weight must be positive: {}", crab));
+            }
+            *positions.entry(pos).or_insert(0) += weight;
+        }
+
+        Ok(Self { positions })
     }
 
-    fn gauss_align_to(&self, pos: isize) -> isize {
+    fn align_to(&self, pos: isize, fuel: &(impl FuelCost + ?Sized)) -> isize {
         self.positions
             .iter()
-            .map(|(p, c)| {
-                let dist = isize::abs(pos - p);
-                let fuel = dist * (dist + 1) / 2;
-                fuel * *c as isize
-            })
+            .map(|(p, c)| fuel.cost(isize::abs(pos - p)) * *c as isize)
             .sum::<isize>()
     }
 
@@ -53,17 +259,86 @@ impl CrabMap {
         *self.positions.keys().min().unwrap()
     }
 
-    fn optimise(&self) -> isize {
+    /// Finds all positions with the least fuel. For a convex cost they follow
+    /// the lowest one, otherwise the whole curve is needed.
+    fn optimise(&self, fuel: &(impl FuelCost + ?Sized)) -> Alignment {
+        if !fuel.is_convex() {
+            return Alignment::from(&self.curve(fuel)[..]);
+        }
+
+        let (lowest, least) = fuel.optimise(self);
+        let positions = (lowest..=self.max_position())
+            .take_while(|pos| self.align_to(*pos, fuel) == least)
+            .collect();
+
+        Alignment {
+            positions,
+            fuel: least,
+        }
+    }
+
+    /// Fuel for every position between the outermost crabs.
+    fn curve(&self, fuel: &(impl FuelCost + ?Sized)) -> Vec<(isize, isize)> {
         (self.min_position()..=self.max_position())
-            .map(|pos| self.align_to(pos))
-            .min()
-            .unwrap()
+            .map(|pos| (pos, self.align_to(pos, fuel)))
+            .collect()
     }
 
-    fn gauss_optimise(&self) -> isize {
+    /// Checks every position between the outermost crabs.
+    fn brute_force(&self, fuel: &(impl FuelCost + ?Sized)) -> (isize, isize) {
         (self.min_position()..=self.max_position())
-            .map(|pos| self.gauss_align_to(pos))
-            .min()
+            .map(|pos| (pos, self.align_to(pos, fuel)))
+            .min_by_key(|(pos, fuel)| (*fuel, *pos))
+            .unwrap()
+    }
+
+    /// Binary search for the first position after which the fuel stops
+    /// falling, which is the lowest optimum if the cost is convex.
+    fn convex_optimise(&self, fuel: &(impl FuelCost + ?Sized)) -> (isize, isize) {
+        let (mut lo, mut hi) = (self.min_position(), self.max_position());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.align_to(mid + 1, fuel) >= self.align_to(mid, fuel) {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+
+        (lo, self.align_to(lo, fuel))
+    }
+
+    /// Returns the lowest optimal position and its fuel for a constant cost
+    /// per step. The sum of distances is minimal at the median.
+    fn median_optimise(&self) -> (isize, isize) {
+        let mut positions: Vec<(&isize, &usize)> = self.positions.iter().collect();
+        positions.sort_unstable();
+
+        let total: usize = self.positions.values().sum();
+        let mut seen = 0;
+        let median = positions
+            .iter()
+            .find(|(_, c)| {
+                seen += **c;
+                2 * seen >= total
+            })
+            .map(|(p, _)| **p)
+            .unwrap();
+
+        (median, self.align_to(median, &Linear))
+    }
+
+    /// Returns the lowest optimal position and its fuel for a cost growing by
+    /// one with every step. The optimum lies within half a step of the mean,
+    /// the cost is convex, so only the neighbouring integers need checking.
+    fn mean_optimise(&self) -> (isize, isize) {
+        let total: isize = self.positions.values().map(|c| *c as isize).sum();
+        let sum: isize = self.positions.iter().map(|(p, c)| p * *c as isize).sum();
+        let mean = sum.div_euclid(total);
+
+        (mean - 1..=mean + 1)
+            .map(|pos| (pos, self.align_to(pos, &Triangular)))
+            .min_by_key(|(pos, fuel)| (*fuel, *pos))
             .unwrap()
     }
 }
@@ -72,6 +347,9 @@ impl Challenge<String> for Day7 {
     fn new(input_file: &str) -> Self {
         Self {
             data: Self::load(input_file).unwrap(),
+            fuel: None,
+            curve: false,
+            curve_to: None,
         }
     }
 
@@ -89,14 +367,47 @@ impl Challenge<String> for Day7 {
 }
 
 impl Day7 {
+    /// Overrides the fuel cost implied by the part.
+    pub fn with_fuel(mut self, fuel: Option<FuelModel>) -> Self {
+        self.fuel = fuel;
+        self
+    }
+
+    /// Writes the cost curve to `curve_to`, or to stdout if no file is given,
+    /// followed by the optimal positions.
+    pub fn with_curve(mut self, curve: bool, curve_to: Option<String>) -> Self {
+        self.curve = curve;
+        self.curve_to = curve_to;
+        self
+    }
+
+    fn write_curve(&self, crabs: &CrabMap, cost: &dyn FuelCost) -> Result<(), String> {
+        let csv = curve_to_csv(&crabs.curve(cost));
+        match &self.curve_to {
+            Some(path) => fs::write(path, csv),
+            None => io::stdout().write_all(csv.as_bytes()),
+        }
+        .map_err(|e| format!("Error while exporting the cost curve: {}", e))
+    }
+
+    fn solve(&self, default: FuelModel) -> Result<String, String> {
+        let crabs = CrabMap::parse(self.data.first().ok_or("No crab positions")?)?;
+        let cost = self.fuel.as_ref().unwrap_or(&default).cost();
+        let alignment = crabs.optimise(cost);
+        if self.curve {
+            self.write_curve(&crabs, cost)?;
+            println!("{}", alignment);
+        }
+
+        Ok(format!("{:#?}", alignment.fuel))
+    }
+
     fn run_part_one(&self) -> Result<String, String> {
-        let crabs = CrabMap::from(&self.data[0][..]);
-        Ok(format!("{:#?}", crabs.optimise()))
+        self.solve(FuelModel::Linear)
     }
 
     fn run_part_two(&self) -> Result<String, String> {
-        let crabs = CrabMap::from(&self.data[0][..]);
-        Ok(format!("{:#?}", crabs.gauss_optimise()))
+        self.solve(FuelModel::Triangular)
     }
 }
 
@@ -105,40 +416,203 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_from() {
+    fn test_parse() {
         let expected = CrabMap {
             positions: HashMap::from([(0, 1), (1, 2), (2, 3), (4, 1), (7, 1), (14, 1), (16, 1)]),
         };
-        let crabs = CrabMap::from("16,1,2,0,4,2,7,1,2,14");
+        let crabs = CrabMap::parse("16,1,2,0,4,2,7,1,2,14").unwrap();
         assert_eq!(expected, crabs);
     }
 
     #[test]
     fn test_align_to() {
-        let crabs = CrabMap::from("16,1,2,0,4,2,7,1,2,14");
-        let fuel = crabs.align_to(1);
+        let crabs = CrabMap::parse("16,1,2,0,4,2,7,1,2,14").unwrap();
+        let fuel = crabs.align_to(1, &Linear);
         assert_eq!(41, fuel);
-        let fuel = crabs.align_to(2);
+        let fuel = crabs.align_to(2, &Linear);
         assert_eq!(37, fuel);
-        let fuel = crabs.align_to(3);
+        let fuel = crabs.align_to(3, &Linear);
         assert_eq!(39, fuel);
+        let fuel = crabs.align_to(2, &Triangular);
+        assert_eq!(206, fuel);
+        let fuel = crabs.align_to(5, &Triangular);
+        assert_eq!(168, fuel);
     }
 
     #[test]
     fn test_max_position() {
-        let crabs = CrabMap::from("16,1,2,0,4,2,7,1,2,14");
+        let crabs = CrabMap::parse("16,1,2,0,4,2,7,1,2,14").unwrap();
         assert_eq!(16, crabs.max_position());
     }
 
     #[test]
     fn test_optimise() {
-        let crabs = CrabMap::from("16,1,2,0,4,2,7,1,2,14");
-        assert_eq!(37, crabs.optimise());
+        let crabs = CrabMap::parse("16,1,2,0,4,2,7,1,2,14").unwrap();
+        assert_eq!((2, 37), crabs.brute_force(&Linear));
+        let expected = Alignment {
+            positions: vec![2],
+            fuel: 37,
+        };
+        assert_eq!(expected, crabs.optimise(&Linear));
     }
 
     #[test]
     fn test_gauss_optimise() {
-        let crabs = CrabMap::from("16,1,2,0,4,2,7,1,2,14");
-        assert_eq!(168, crabs.gauss_optimise());
+        let crabs = CrabMap::parse("16,1,2,0,4,2,7,1,2,14").unwrap();
+        assert_eq!((5, 168), crabs.brute_force(&Triangular));
+        let expected = Alignment {
+            positions: vec![5],
+            fuel: 168,
+        };
+        assert_eq!(expected, crabs.optimise(&Triangular));
+    }
+
+    #[test]
+    fn test_median_optimise() {
+        let crabs = CrabMap::parse("16,1,2,0,4,2,7,1,2,14").unwrap();
+        assert_eq!((2, 37), crabs.median_optimise());
+        let crabs = CrabMap::parse("1,5").unwrap();
+        assert_eq!((1, 4), crabs.median_optimise());
+        let crabs = CrabMap::parse("-3,-3,8").unwrap();
+        assert_eq!((-3, 11), crabs.median_optimise());
+    }
+
+    #[test]
+    fn test_mean_optimise() {
+        let crabs = CrabMap::parse("16,1,2,0,4,2,7,1,2,14").unwrap();
+        assert_eq!((5, 168), crabs.mean_optimise());
+        let crabs = CrabMap::parse("-7,-2").unwrap();
+        assert_eq!((-5, 9), crabs.mean_optimise());
+    }
+
+    #[test]
+    fn test_parse_weighted() {
+        let expected = CrabMap {
+            positions: HashMap::from([(0, 3), (4, 1), (7, 2)]),
+        };
+        let crabs = CrabMap::parse("0:2,4,7:2,0").unwrap();
+        assert_eq!(expected, crabs);
+
+        assert!(CrabMap::parse("4:x").is_err());
+        assert!(CrabMap::parse("1,x").is_err());
+        assert_eq!(
+            Err(String::from("Crab weight must be positive: 5:0")),
+            CrabMap::parse("5:0")
+        );
+    }
+
+    #[test]
+    fn test_weighted_optimise() {
+        // A heavy crab pulls the optimum towards itself.
+        let crabs = CrabMap::parse("0,10:5").unwrap();
+        assert_eq!(vec![10], crabs.optimise(&Linear).positions);
+        assert_eq!((9, 50), crabs.mean_optimise());
+        assert_eq!((9, 50), crabs.brute_force(&Triangular));
+        assert_eq!(
+            crabs.brute_force(&Quadratic),
+            crabs.convex_optimise(&Quadratic)
+        );
+    }
+
+    #[test]
+    fn test_weight_table() {
+        let table = WeightTable::new(&[1, 2, 4]).unwrap();
+        let costs: Vec<isize> = (0..6).map(|d| table.cost(d)).collect();
+        assert_eq!(vec![0, 1, 3, 7, 11, 15], costs);
+        assert!(table.is_convex());
+        assert!(!WeightTable::new(&[3, 1]).unwrap().is_convex());
+        assert!(WeightTable::new(&[]).is_err());
+        assert_eq!(
+            Err(String::from("Negative weight -5 in the weight table")),
+            WeightTable::new(&[-5, 1])
+        );
+
+        let crabs = CrabMap::parse("16,1,2,0,4,2,7,1,2,14").unwrap();
+        let table = WeightTable::new(&[1]).unwrap();
+        assert_eq!(37, crabs.optimise(&table).fuel);
+
+        // Falling weights reward moving far, so the search is exhaustive.
+        let table = WeightTable::new(&[10, 1]).unwrap();
+        assert!(!table.is_convex());
+        assert_eq!(
+            Alignment::from(&crabs.curve(&table)[..]),
+            crabs.optimise(&table)
+        );
+    }
+
+    #[test]
+    fn test_fuel_model_from_str() {
+        assert_eq!(Ok(FuelModel::Linear), FuelModel::from_str("linear"));
+        assert_eq!(Ok(FuelModel::Triangular), FuelModel::from_str("triangular"));
+        assert_eq!(Ok(FuelModel::Quadratic), FuelModel::from_str("quadratic"));
+        assert_eq!(
+            Ok(FuelModel::Table(WeightTable::new(&[1, 2, 4]).unwrap())),
+            FuelModel::from_str("table:1,2,4")
+        );
+        assert!(FuelModel::from_str("table:").is_err());
+        assert!(FuelModel::from_str("table:-5,1").is_err());
+        assert!(FuelModel::from_str("cubic").is_err());
+
+        let crabs = CrabMap::parse("16,1,2,0,4,2,7,1,2,14").unwrap();
+        assert_eq!(168, crabs.optimise(FuelModel::Triangular.cost()).fuel);
+    }
+
+    #[test]
+    fn test_optimise_ties() {
+        // Any position between an even number of crabs is optimal.
+        let crabs = CrabMap::parse("1,5").unwrap();
+        let expected = Alignment {
+            positions: vec![1, 2, 3, 4, 5],
+            fuel: 4,
+        };
+        assert_eq!(expected, crabs.optimise(&Linear));
+        assert_eq!(
+            "Align at 1 or 2 or 3 or 4 or 5 with 4 fuel",
+            expected.to_string()
+        );
+
+        let crabs = CrabMap::parse("0,3").unwrap();
+        let expected = Alignment {
+            positions: vec![1, 2],
+            fuel: 4,
+        };
+        assert_eq!(expected, crabs.optimise(&Triangular));
+        assert_eq!(expected, Alignment::from(&crabs.curve(&Triangular)[..]));
+    }
+
+    #[test]
+    fn test_curve() {
+        let crabs = CrabMap::parse("0,3").unwrap();
+        let curve = crabs.curve(&Linear);
+        assert_eq!(vec![(0, 3), (1, 3), (2, 3), (3, 3)], curve);
+        assert_eq!("position,fuel\n0,3\n1,3\n2,3\n3,3\n", curve_to_csv(&curve));
+    }
+
+    #[test]
+    fn test_optimise_matches_brute_force() {
+        let mut state: u64 = 7;
+        for _ in 0..50 {
+            let crabs: Vec<String> = (0..20)
+                .map(|_| {
+                    state = state
+                        .wrapping_mul(6364136223846793005)
+                        .wrapping_add(1442695040888963407);
+                    ((state >> 33) % 100).to_string()
+                })
+                .collect();
+            let crabs = CrabMap::parse(&crabs.join(",")[..]).unwrap();
+            assert_eq!(crabs.brute_force(&Linear), crabs.median_optimise());
+            assert_eq!(crabs.brute_force(&Triangular), crabs.mean_optimise());
+            assert_eq!(
+                crabs.brute_force(&Quadratic),
+                crabs.convex_optimise(&Quadratic)
+            );
+            for cost in [&Linear as &dyn FuelCost, &Triangular, &Quadratic].iter() {
+                assert_eq!(
+                    Alignment::from(&crabs.curve(*cost)[..]),
+                    crabs.optimise(*cost)
+                );
+            }
+        }
     }
 }
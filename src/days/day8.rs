@@ -1,143 +1,423 @@
 use crate::utils::challenge::Challenge;
-use std::collections::{HashMap, HashSet};
-use std::iter::FromIterator;
+use std::fmt;
+use std::str::FromStr;
+
+/// Wires and segments are both named `a` to `g`.
+const SEGMENTS: &str = "abcdefg";
+
+/// Lit wires or segments as bits, `a` being the lowest.
+type Segments = u8;
+
+/// Encodes the letters `a` to `g` of a pattern as bits.
+const fn mask(pattern: &str) -> Segments {
+    let bytes = pattern.as_bytes();
+    let mut bits = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] >= b'a' && bytes[i] <= b'g' {
+            bits |= 1 << (bytes[i] - b'a');
+        }
+        i += 1;
+    }
+    bits
+}
+
+/// Lit segments of the digits 0 to 9 on a correctly wired display.
+const DIGITS: [Segments; 10] = [
+    mask("abcefg"),
+    mask("cf"),
+    mask("acdeg"),
+    mask("acdfg"),
+    mask("bcdf"),
+    mask("abdfg"),
+    mask("abdefg"),
+    mask("acf"),
+    mask("abcdefg"),
+    mask("abcdfg"),
+];
+
+/// The digit shown by each combination of lit segments, if any.
+const DIGIT_OF: [Option<u8>; 128] = lookup(&DIGITS);
+
+/// Builds a table from the segments of each digit back to the digit.
+const fn lookup(digits: &[Segments; 10]) -> [Option<u8>; 128] {
+    let mut table = [None; 128];
+    let mut digit = 0;
+    while digit < 10 {
+        table[digits[digit] as usize] = Some(digit as u8);
+        digit += 1;
+    }
+    table
+}
+
+/// The segment each wire `a` to `g` is connected to, by index.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Wiring([u8; 7]);
+
+impl Wiring {
+    /// Maps the lit wires among the first `assigned.len()` wires to their
+    /// segments, ignoring the wires not assigned yet.
+    fn apply_partial(assigned: &[u8], wires: Segments) -> Segments {
+        assigned
+            .iter()
+            .enumerate()
+            .filter(|(wire, _)| wires & 1 << wire != 0)
+            .fold(0, |lit, (_, seg)| lit | 1 << seg)
+    }
+
+    fn apply(&self, wires: Segments) -> Segments {
+        Self::apply_partial(&self.0, wires)
+    }
+
+    /// The digit shown when the given wires are lit, if any.
+    fn digit(&self, wires: Segments) -> Option<u8> {
+        DIGIT_OF[self.apply(wires) as usize]
+    }
+
+    /// The wire connected to a segment.
+    fn wire(&self, segment: u8) -> u8 {
+        self.0.iter().position(|s| *s == segment).unwrap() as u8
+    }
+
+    /// The wires lit for a digit.
+    fn wires(&self, digit: u8) -> Segments {
+        self.0
+            .iter()
+            .enumerate()
+            .filter(|(_, seg)| DIGITS[digit as usize] & 1 << *seg != 0)
+            .fold(0, |wires, (wire, _)| wires | 1 << wire)
+    }
+}
+
+#[cfg(test)]
+impl FromStr for Wiring {
+    type Err = String;
+
+    /// Parses the segments of the wires `a` to `g` like `cfgabde`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != SEGMENTS.len() || mask(s) != mask(SEGMENTS) {
+            return Err(format!("Invalid wiring: {}", s));
+        }
+
+        let mut wiring = [0; 7];
+        for (wire, seg) in s.bytes().enumerate() {
+            wiring[wire] = seg - b'a';
+        }
+        Ok(Self(wiring))
+    }
+}
+
+impl fmt::Display for Wiring {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let segments: String = self.0.iter().map(|s| (b'a' + s) as char).collect();
+        write!(f, "{} -> {}", SEGMENTS, segments)
+    }
+}
+
+/// Writes a note line showing all digits on a display with the given wiring
+/// followed by the digits to output.
+#[cfg(test)]
+fn encode(wiring: &Wiring, digits: &[u8]) -> Result<String, String> {
+    let pattern = |digit: &u8| -> Result<String, String> {
+        if *digit > 9 {
+            return Err(format!("Invalid digit: {}", digit));
+        }
+        let wires = wiring.wires(*digit);
+        Ok(SEGMENTS
+            .chars()
+            .enumerate()
+            .filter(|(wire, _)| wires & 1 << wire != 0)
+            .map(|(_, c)| c)
+            .collect())
+    };
+
+    let input = (0..10)
+        .map(|d| pattern(&d))
+        .collect::<Result<Vec<_>, _>>()?;
+    let output = digits.iter().map(pattern).collect::<Result<Vec<_>, _>>()?;
+    Ok(format!("{} | {}", input.join(" "), output.join(" ")))
+}
+
+/// Draws digits side by side, labelling each lit segment with the wire
+/// driving it and marking unlit segments with `.`.
+fn render_digits(wiring: &Wiring, digits: &[u8]) -> String {
+    let mut rows = vec![Vec::new(); 7];
+    for digit in digits {
+        let label = |seg: u8| {
+            if DIGITS[*digit as usize] & 1 << seg != 0 {
+                (b'a' + wiring.wire(seg)) as char
+            } else {
+                '.'
+            }
+        };
+        let horizontal = |seg: u8| format!(" {} ", label(seg).to_string().repeat(4));
+        let vertical = |left: u8, right: u8| format!("{}    {}", label(left), label(right));
+
+        rows[0].push(horizontal(0));
+        rows[1].push(vertical(1, 2));
+        rows[2].push(vertical(1, 2));
+        rows[3].push(horizontal(3));
+        rows[4].push(vertical(4, 5));
+        rows[5].push(vertical(4, 5));
+        rows[6].push(horizontal(6));
+    }
+
+    rows.iter()
+        .map(|row| row.join("  ").trim_end().to_string())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
 
 #[derive(Debug, PartialEq)]
 struct DisplayPattern {
-    mapping: HashMap<u8, HashSet<char>>,
-    input: Vec<HashSet<char>>,
-    output: Vec<HashSet<char>>,
+    /// The wires lit for each digit.
+    mapping: [Segments; 10],
+    /// The digit shown by each combination of lit wires.
+    digits: [Option<u8>; 128],
+    input: Vec<Segments>,
+    output: Vec<Segments>,
 }
 
 impl From<&str> for DisplayPattern {
+    /// Reads notes without validating them, so partial notes can be solved.
+    /// A missing separator leaves the output empty.
     fn from(line: &str) -> Self {
-        let sep: Vec<&str> = line.split('|').collect();
+        let mut sep = line.splitn(2, '|');
 
-        let input: Vec<HashSet<char>> = sep
-            .get(0)
+        let input: Vec<Segments> = sep
+            .next()
             .unwrap()
             .split(' ')
-            .map(|e| HashSet::<char>::from_iter(e.chars()))
             .filter(|e| !e.is_empty())
+            .map(mask)
             .collect();
-        let output: Vec<HashSet<char>> = sep
-            .get(1)
-            .unwrap()
+        let output: Vec<Segments> = sep
+            .next()
+            .unwrap_or("")
             .split(' ')
-            .map(|e| HashSet::<char>::from_iter(e.chars()))
             .filter(|e| !e.is_empty())
+            .map(mask)
             .collect();
         Self {
-            mapping: HashMap::new(),
+            mapping: [0; 10],
+            digits: [None; 128],
             input,
             output,
         }
     }
 }
 
+/// Checks that a pattern only lights each of the segments `a` to `g` once.
+fn validate_pattern(pattern: &str) -> Result<(), String> {
+    if let Some(c) = pattern.chars().find(|c| !SEGMENTS.contains(*c)) {
+        return Err(format!("Invalid segment {:?} in pattern {}", c, pattern));
+    }
+    if mask(pattern).count_ones() as usize != pattern.len() {
+        return Err(format!("Repeated segment in pattern {}", pattern));
+    }
+
+    Ok(())
+}
+
 impl DisplayPattern {
+    /// Reads notes of exactly ten distinct signal patterns and four outputs.
+    fn parse(line: &str) -> Result<Self, String> {
+        let separators = line.matches('|').count();
+        if separators != 1 {
+            return Err(format!("Expected one '|' separator, found {}", separators));
+        }
+        for pattern in line.split(&[' ', '|'][..]) {
+            validate_pattern(pattern)?;
+        }
+
+        let disp = Self::from(line);
+        if disp.input.len() != 10 {
+            return Err(format!(
+                "Expected 10 signal patterns, found {}",
+                disp.input.len()
+            ));
+        }
+        if disp.output.len() != 4 {
+            return Err(format!(
+                "Expected 4 output patterns, found {}",
+                disp.output.len()
+            ));
+        }
+        let distinct = disp
+            .input
+            .iter()
+            .fold(0u128, |seen, p| seen | 1 << p)
+            .count_ones();
+        if distinct != 10 {
+            return Err(String::from("Repeated signal pattern"));
+        }
+
+        Ok(disp)
+    }
+
     fn count_out_by_len(&self, len: usize) -> usize {
         self.output
             .iter()
-            .fold(0, |acc, item| if item.len() == len { acc + 1 } else { acc })
+            .filter(|item| item.count_ones() as usize == len)
+            .count()
     }
 
-    fn find_by_len(&self, len: usize) -> HashSet<char> {
-        self.input
-            .iter()
-            .find(|&item| item.len() == len)
-            .unwrap()
-            .clone()
+    /// Whether the wires assigned so far can still light a digit for every
+    /// pattern: an assigned wire must be in the pattern exactly if its segment
+    /// is in the digit.
+    fn is_consistent(&self, assigned: &[u8]) -> bool {
+        let known: Segments = (1 << assigned.len()) - 1;
+        let used = Wiring::apply_partial(assigned, known);
+        self.input.iter().chain(self.output.iter()).all(|pattern| {
+            let lit = Wiring::apply_partial(assigned, pattern & known);
+            DIGITS
+                .iter()
+                .any(|digit| digit.count_ones() == pattern.count_ones() && digit & used == lit)
+        })
     }
 
-    fn filter_by_len(&self, len: usize) -> Vec<HashSet<char>> {
-        self.input
-            .iter()
-            .filter(|item| item.len() == len)
-            .cloned()
-            .collect()
-    }
-
-    fn fill_mapping(&mut self) {
-        let one = self.find_by_len(2);
-        let four = self.find_by_len(4);
-        let seven = self.find_by_len(3);
-        let eight = self.find_by_len(7);
-
-        // 2 3 5
-        let five_segs = self.filter_by_len(5);
-        let mut two = HashSet::<char>::new();
-        let mut three = HashSet::<char>::new();
-        let mut five = HashSet::<char>::new();
-
-        // 0 6 9
-        let six_segs = self.filter_by_len(6);
-        let mut zero = HashSet::<char>::new();
-        let mut six = HashSet::<char>::new();
-        let mut nine = HashSet::<char>::new();
-
-        for cand in five_segs {
-            if cand.difference(&one).count() == 3 {
-                three = cand.clone();
-            } else if cand.difference(&four).count() == 2 {
-                five = cand.clone();
-            } else {
-                two = cand.clone();
+    /// Extends the assignment of wires to segments depth first, collecting up
+    /// to two complete wirings under which all patterns show digits.
+    fn search(&self, assigned: &mut Vec<u8>, found: &mut Vec<Wiring>) {
+        if found.len() > 1 {
+            return;
+        }
+        if assigned.len() == SEGMENTS.len() {
+            let mut wiring = [0; 7];
+            wiring.copy_from_slice(assigned);
+            let wiring = Wiring(wiring);
+            let shown = self
+                .input
+                .iter()
+                .filter_map(|p| wiring.digit(*p))
+                .fold(0u16, |shown, d| shown | 1 << d);
+            if shown.count_ones() as usize == self.input.len() {
+                found.push(wiring);
             }
+            return;
         }
 
-        for cand in six_segs {
-            if cand.difference(&one).count() == 5 {
-                six = cand.clone();
-            } else if cand.difference(&four).count() == 2 {
-                nine = cand.clone();
-            } else {
-                zero = cand.clone();
+        for seg in 0..SEGMENTS.len() as u8 {
+            if !assigned.contains(&seg) {
+                assigned.push(seg);
+                if self.is_consistent(assigned) {
+                    self.search(assigned, found);
+                }
+                assigned.pop();
             }
         }
+    }
 
-        self.mapping.insert(0, zero);
-        self.mapping.insert(1, one);
-        self.mapping.insert(2, two);
-        self.mapping.insert(3, three);
-        self.mapping.insert(4, four);
-        self.mapping.insert(5, five);
-        self.mapping.insert(6, six);
-        self.mapping.insert(7, seven);
-        self.mapping.insert(8, eight);
-        self.mapping.insert(9, nine);
-    }
-
-    fn decode_digit(&self, sig: &HashSet<char>) -> usize {
-        self.mapping.iter().fold(0, |acc, (num, set)| {
-            if sig.symmetric_difference(set).count() == 0 {
-                *num as usize
-            } else {
-                acc
-            }
-        })
+    /// Finds the only wiring under which the signal patterns show distinct
+    /// digits and the outputs show digits.
+    fn solve(&self) -> Result<Wiring, String> {
+        let mut found = Vec::new();
+        self.search(&mut Vec::new(), &mut found);
+        match found[..] {
+            [wiring] => Ok(wiring),
+            [] => Err(String::from("Contradictory notes: no wiring fits")),
+            _ => Err(format!(
+                "Ambiguous notes: both {} and {} fit",
+                found[0], found[1]
+            )),
+        }
+    }
+
+    fn fill_mapping(&mut self) -> Result<Wiring, String> {
+        let wiring = self.solve()?;
+        for digit in 0..10 {
+            let wires = wiring.wires(digit);
+            self.mapping[digit as usize] = wires;
+            self.digits[wires as usize] = Some(digit);
+        }
+
+        Ok(wiring)
+    }
+
+    fn decode_digit(&self, sig: Segments) -> usize {
+        self.digits[sig as usize].unwrap_or(0) as usize
+    }
+
+    /// Draws the output once the wiring is known.
+    fn render(&self) -> Result<String, String> {
+        let wiring = self
+            .solve()
+            .map_err(|e| format!("Cannot draw undecodable notes: {}", e))?;
+        let digits: Vec<u8> = self
+            .output
+            .iter()
+            .map(|o| wiring.digit(*o).unwrap())
+            .collect();
+
+        Ok(render_digits(&wiring, &digits))
     }
 
     fn decode(&self) -> usize {
         self.output.iter().enumerate().fold(0, |acc, (i, val)| {
-            acc + usize::pow(10, (self.output.len() - i - 1) as u32) * self.decode_digit(val)
+            acc + usize::pow(10, (self.output.len() - i - 1) as u32) * self.decode_digit(*val)
         })
     }
 }
 
-struct Display {
-    data: Vec<DisplayPattern>,
+/// What to do with note lines that cannot be read or decoded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LinePolicy {
+    Skip,
+    Reject,
 }
 
-impl From<&[String]> for Display {
-    fn from(lines: &[String]) -> Self {
-        let data: Vec<DisplayPattern> =
-            lines.iter().map(|x| DisplayPattern::from(&x[..])).collect();
-        Self { data }
+impl FromStr for LinePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(Self::Skip),
+            "reject" => Ok(Self::Reject),
+            x => Err(format!("Unknown line policy: {}", x)),
+        }
+    }
+}
+
+impl LinePolicy {
+    /// Passes the error on when rejecting, otherwise reports the skipped line.
+    fn handle(&self, line: usize, err: String) -> Result<(), String> {
+        match self {
+            Self::Skip => {
+                eprintln!("Skipping line {}: {}", line, err);
+                Ok(())
+            }
+            Self::Reject => Err(format!("Line {}: {}", line, err)),
+        }
     }
 }
 
+struct Display {
+    data: Vec<DisplayPattern>,
+    /// The line number of each pattern.
+    lines: Vec<usize>,
+    policy: LinePolicy,
+}
+
 impl Display {
+    fn parse(lines: &[String], policy: LinePolicy) -> Result<Self, String> {
+        let mut display = Self {
+            data: Vec::new(),
+            lines: Vec::new(),
+            policy,
+        };
+        for (i, line) in lines.iter().enumerate() {
+            match DisplayPattern::parse(line) {
+                Ok(disp) => {
+                    display.data.push(disp);
+                    display.lines.push(i + 1);
+                }
+                Err(err) => policy.handle(i + 1, err)?,
+            }
+        }
+
+        Ok(display)
+    }
+
     fn count_out_by_len(&self) -> usize {
         let mut occurrences = 0;
 
@@ -150,25 +430,39 @@ impl Display {
         occurrences
     }
 
-    fn deduce(&mut self) -> usize {
+    /// Sums the decoded outputs and returns the wiring found for each line
+    /// by line number.
+    fn deduce(&mut self) -> Result<(usize, Vec<(usize, Wiring)>), String> {
         let mut sum = 0;
-        for disp in self.data.iter_mut() {
-            disp.fill_mapping();
-            sum += disp.decode();
+        let mut wirings = Vec::new();
+        for (disp, line) in self.data.iter_mut().zip(&self.lines) {
+            match disp.fill_mapping() {
+                Ok(wiring) => {
+                    wirings.push((*line, wiring));
+                    sum += disp.decode();
+                }
+                Err(err) => self.policy.handle(*line, err)?,
+            }
         }
 
-        sum
+        Ok((sum, wirings))
     }
 }
 
 pub struct Day8 {
     data: Vec<String>,
+    wirings: bool,
+    draw: bool,
+    policy: LinePolicy,
 }
 
 impl Challenge<String> for Day8 {
     fn new(input_file: &str) -> Self {
         Self {
             data: Self::load(input_file).unwrap(),
+            wirings: false,
+            draw: false,
+            policy: LinePolicy::Reject,
         }
     }
 
@@ -186,14 +480,47 @@ impl Challenge<String> for Day8 {
 }
 
 impl Day8 {
+    /// Prints the wiring found for each line.
+    pub fn with_wirings(mut self, wirings: bool) -> Self {
+        self.wirings = wirings;
+        self
+    }
+
+    /// Draws the decoded output of each line.
+    pub fn with_draw(mut self, draw: bool) -> Self {
+        self.draw = draw;
+        self
+    }
+
+    /// Skips or rejects invalid and undecodable lines.
+    pub fn with_policy(mut self, policy: LinePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
     fn run_part_one(&self) -> Result<String, String> {
-        let disp_lines = Display::from(&self.data[..]);
+        let disp_lines = Display::parse(&self.data[..], self.policy)?;
         Ok(format!("{:#?}", disp_lines.count_out_by_len()))
     }
 
     fn run_part_two(&self) -> Result<String, String> {
-        let mut disp_lines = Display::from(&self.data[..]);
-        Ok(format!("{:#?}", disp_lines.deduce()))
+        let mut disp_lines = Display::parse(&self.data[..], self.policy)?;
+        let (sum, wirings) = disp_lines.deduce()?;
+        if self.wirings {
+            for (line, wiring) in wirings {
+                println!("Line {}: {}", line, wiring);
+            }
+        }
+        if self.draw {
+            // Undecodable lines have already been skipped or rejected.
+            for (disp, line) in disp_lines.data.iter().zip(&disp_lines.lines) {
+                if let Ok(art) = disp.render() {
+                    println!("Line {}:\n{}\n", line, art);
+                }
+            }
+        }
+
+        Ok(format!("{:#?}", sum))
     }
 }
 
@@ -203,18 +530,19 @@ mod tests {
 
     #[test]
     fn test_from() {
-        let input: Vec<HashSet<char>> = [
+        let input: Vec<Segments> = [
             "be", "cfbegad", "cbdgef", "fgaecd", "cgeb", "fdcge", "agebfd", "fecdb", "fabcd", "edb",
         ]
         .iter()
-        .map(|e| HashSet::from_iter(e.chars()))
+        .map(|e| mask(e))
         .collect();
-        let output: Vec<HashSet<char>> = ["fdgacbe", "cefdb", "cefbgd", "gcbe"]
+        let output: Vec<Segments> = ["fdgacbe", "cefdb", "cefbgd", "gcbe"]
             .iter()
-            .map(|e| HashSet::from_iter(e.chars()))
+            .map(|e| mask(e))
             .collect();
         let expected = DisplayPattern {
-            mapping: HashMap::new(),
+            mapping: [0; 10],
+            digits: [None; 128],
             input,
             output,
         };
@@ -239,47 +567,27 @@ mod tests {
             "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf",
         );
 
-        disp.fill_mapping();
+        disp.fill_mapping().unwrap();
 
-        let actual = disp.mapping.get(&0).unwrap();
-        let expected: HashSet<char> = HashSet::from_iter("cagedb".chars());
-        assert!(actual.symmetric_difference(&expected).count() == 0);
+        assert_eq!(mask("cagedb"), disp.mapping[0]);
 
-        let actual = disp.mapping.get(&1).unwrap();
-        let expected: HashSet<char> = HashSet::from_iter("ab".chars());
-        assert!(actual.symmetric_difference(&expected).count() == 0);
+        assert_eq!(mask("ab"), disp.mapping[1]);
 
-        let actual = disp.mapping.get(&2).unwrap();
-        let expected: HashSet<char> = HashSet::from_iter("gcdfa".chars());
-        assert!(actual.symmetric_difference(&expected).count() == 0);
+        assert_eq!(mask("gcdfa"), disp.mapping[2]);
 
-        let actual = disp.mapping.get(&3).unwrap();
-        let expected: HashSet<char> = HashSet::from_iter("fbcad".chars());
-        assert!(actual.symmetric_difference(&expected).count() == 0);
+        assert_eq!(mask("fbcad"), disp.mapping[3]);
 
-        let actual = disp.mapping.get(&4).unwrap();
-        let expected: HashSet<char> = HashSet::from_iter("eafb".chars());
-        assert!(actual.symmetric_difference(&expected).count() == 0);
+        assert_eq!(mask("eafb"), disp.mapping[4]);
 
-        let actual = disp.mapping.get(&5).unwrap();
-        let expected: HashSet<char> = HashSet::from_iter("cdfbe".chars());
-        assert!(actual.symmetric_difference(&expected).count() == 0);
+        assert_eq!(mask("cdfbe"), disp.mapping[5]);
 
-        let actual = disp.mapping.get(&6).unwrap();
-        let expected: HashSet<char> = HashSet::from_iter("cdfgeb".chars());
-        assert!(actual.symmetric_difference(&expected).count() == 0);
+        assert_eq!(mask("cdfgeb"), disp.mapping[6]);
 
-        let actual = disp.mapping.get(&7).unwrap();
-        let expected: HashSet<char> = HashSet::from_iter("dab".chars());
-        assert!(actual.symmetric_difference(&expected).count() == 0);
+        assert_eq!(mask("dab"), disp.mapping[7]);
 
-        let actual = disp.mapping.get(&8).unwrap();
-        let expected: HashSet<char> = HashSet::from_iter("acedgfb".chars());
-        assert!(actual.symmetric_difference(&expected).count() == 0);
+        assert_eq!(mask("acedgfb"), disp.mapping[8]);
 
-        let actual = disp.mapping.get(&9).unwrap();
-        let expected: HashSet<char> = HashSet::from_iter("cefabd".chars());
-        assert!(actual.symmetric_difference(&expected).count() == 0);
+        assert_eq!(mask("cefabd"), disp.mapping[9]);
     }
 
     #[test]
@@ -288,12 +596,10 @@ mod tests {
             "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf",
         );
 
-        disp.fill_mapping();
-        let input: HashSet<char> = HashSet::from_iter("cdfeb".chars());
-        let actual = disp.decode_digit(&input);
+        disp.fill_mapping().unwrap();
+        let actual = disp.decode_digit(mask("cdfeb"));
         assert_eq!(5, actual);
-        let input: HashSet<char> = HashSet::from_iter("fcadb".chars());
-        let actual = disp.decode_digit(&input);
+        let actual = disp.decode_digit(mask("fcadb"));
         assert_eq!(3, actual);
     }
 
@@ -303,8 +609,206 @@ mod tests {
             "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf",
         );
 
-        disp.fill_mapping();
+        disp.fill_mapping().unwrap();
         let actual = disp.decode();
         assert_eq!(5353, actual);
     }
+
+    #[test]
+    fn test_mask() {
+        assert_eq!(0b1111111, mask("gfedcba"));
+        assert_eq!(0b0100101, mask("acf"));
+        assert_eq!(0, mask(""));
+        for (digit, segments) in DIGITS.iter().enumerate() {
+            assert_eq!(Some(digit as u8), DIGIT_OF[*segments as usize]);
+        }
+        assert_eq!(10, DIGIT_OF.iter().filter(|d| d.is_some()).count());
+    }
+
+    #[test]
+    fn test_solve() {
+        let disp = DisplayPattern::from(
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf",
+        );
+        let wiring = disp.solve().unwrap();
+        assert_eq!(Wiring([2, 5, 6, 0, 1, 3, 4]), wiring);
+        assert_eq!("abcdefg -> cfgabde", wiring.to_string());
+        assert_eq!(mask("cfgabde"), wiring.apply(mask("abcdefg")));
+        assert_eq!(mask("dab"), wiring.wires(7));
+        assert_eq!(Some(7), wiring.digit(mask("dab")));
+        assert_eq!(None, wiring.digit(mask("da")));
+    }
+
+    #[test]
+    fn test_render() {
+        let disp = DisplayPattern::from(
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf",
+        );
+        let expected = [
+            " dddd    dddd    dddd    dddd",
+            "e    .  .    a  e    .  .    a",
+            "e    .  .    a  e    .  .    a",
+            " ffff    ffff    ffff    ffff",
+            ".    b  .    b  .    b  .    b",
+            ".    b  .    b  .    b  .    b",
+            " cccc    cccc    cccc    cccc",
+        ]
+        .join("\n");
+        assert_eq!(expected, disp.render().unwrap());
+
+        let wiring: Wiring = "abcdefg".parse().unwrap();
+        let expected = [
+            " aaaa    ....",
+            "b    c  .    c",
+            "b    c  .    c",
+            " ....    ....",
+            "e    f  .    f",
+            "e    f  .    f",
+            " gggg    ....",
+        ]
+        .join("\n");
+        assert_eq!(expected, render_digits(&wiring, &[0, 1]));
+
+        assert!(DisplayPattern::from("ab ac | ab").render().is_err());
+    }
+
+    #[test]
+    fn test_encode() {
+        let wiring: Wiring = "cfgabde".parse().unwrap();
+        let line = encode(&wiring, &[5, 3, 5, 3]).unwrap();
+        assert_eq!(
+            "abcdeg ab acdfg abcdf abef bcdef bcdefg abd abcdefg abcdef | bcdef abcdf bcdef abcdf",
+            line
+        );
+        let disp = DisplayPattern::from(&line[..]);
+        assert_eq!(Ok(wiring), disp.solve());
+        assert!(encode(&wiring, &[10]).is_err());
+        assert!("abcdeff".parse::<Wiring>().is_err());
+        assert!("abc".parse::<Wiring>().is_err());
+    }
+
+    #[test]
+    fn test_encode_decode_roundtrip() {
+        let mut state: u64 = 8;
+        for _ in 0..50 {
+            let mut segments: Vec<u8> = (0..7).collect();
+            for i in (1..7).rev() {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                segments.swap(i, (state >> 33) as usize % (i + 1));
+            }
+            let mut wiring = [0; 7];
+            wiring.copy_from_slice(&segments);
+            let wiring = Wiring(wiring);
+            let digits: Vec<u8> = (0..4).map(|i| ((state >> (8 * i)) % 10) as u8).collect();
+
+            let mut disp = DisplayPattern::from(&encode(&wiring, &digits).unwrap()[..]);
+            assert_eq!(Ok(wiring), disp.fill_mapping());
+            let expected = digits.iter().fold(0, |acc, d| acc * 10 + *d as usize);
+            assert_eq!(expected, disp.decode());
+        }
+    }
+
+    #[test]
+    fn test_solve_ambiguous() {
+        // Without the 1 and the 7 the wires of the segments c and f can swap.
+        let disp =
+            DisplayPattern::from("acedgfb cdfbe gcdfa fbcad cefabd cdfgeb eafb cagedb | cdfeb");
+        let err = disp.solve().unwrap_err();
+        assert!(err.starts_with("Ambiguous notes"), "{}", err);
+    }
+
+    #[test]
+    fn test_solve_contradictory() {
+        // Two patterns claim to be the 1.
+        let disp = DisplayPattern::from(
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ae | cdfeb",
+        );
+        assert!(disp.solve().is_err());
+        let disp = DisplayPattern::from("ab ac | ab");
+        assert_eq!(
+            Err(String::from("Contradictory notes: no wiring fits")),
+            disp.solve()
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let line =
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf";
+        assert_eq!(Ok(DisplayPattern::from(line)), DisplayPattern::parse(line));
+
+        let invalid = [
+            (
+                "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab",
+                "Expected one '|' separator, found 0",
+            ),
+            (
+                "acedgfb cdfbe | gcdfa | fbcad",
+                "Expected one '|' separator, found 2",
+            ),
+            (
+                "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb | cdfeb fcadb cdfeb cdbaf",
+                "Expected 10 signal patterns, found 9",
+            ),
+            (
+                "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb",
+                "Expected 4 output patterns, found 3",
+            ),
+            (
+                "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ba ab | cdfeb fcadb cdfeb cdbaf",
+                "Expected 10 signal patterns, found 11",
+            ),
+            (
+                "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ba | cdfeb fcadb cdfeb cdbax",
+                "Invalid segment 'x' in pattern cdbax",
+            ),
+            (
+                "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb abb | cdfeb fcadb cdfeb cdbaf",
+                "Repeated segment in pattern abb",
+            ),
+            (
+                "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb dba | cdfeb fcadb cdfeb cdbaf",
+                "Repeated signal pattern",
+            ),
+        ];
+        for (line, err) in invalid.iter() {
+            assert_eq!(Err(String::from(*err)), DisplayPattern::parse(line));
+        }
+    }
+
+    #[test]
+    fn test_line_policy() {
+        let lines = [
+            String::from("acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf"),
+            String::from("ab ac | ab"),
+            String::from("acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ae | cdfeb fcadb cdfeb cdbaf"),
+            String::from("be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd edb | fdgacbe cefdb cefbgd gcbe"),
+        ];
+
+        assert_eq!(
+            Err(String::from("Line 2: Expected 10 signal patterns, found 2")),
+            Display::parse(&lines, LinePolicy::Reject).map(|d| d.lines)
+        );
+
+        let mut display = Display::parse(&lines[2..], LinePolicy::Reject).unwrap();
+        assert_eq!(
+            Err(String::from("Line 1: Contradictory notes: no wiring fits")),
+            display.deduce()
+        );
+
+        let mut display = Display::parse(&lines, LinePolicy::Skip).unwrap();
+        assert_eq!(vec![1, 3, 4], display.lines);
+        let (sum, wirings) = display.deduce().unwrap();
+        assert_eq!(5353 + 8394, sum);
+        assert_eq!(
+            vec![1, 4],
+            wirings.iter().map(|(l, _)| *l).collect::<Vec<_>>()
+        );
+
+        assert_eq!(Ok(LinePolicy::Skip), LinePolicy::from_str("skip"));
+        assert_eq!(Ok(LinePolicy::Reject), LinePolicy::from_str("reject"));
+        assert!(LinePolicy::from_str("ignore").is_err());
+    }
 }
@@ -0,0 +1,166 @@
+use crate::utils::challenge::Challenge;
+use crate::utils::grid::{Adjacency, Grid};
+
+pub struct Day9 {
+    data: Vec<String>,
+    adjacency: Adjacency,
+}
+
+#[derive(Debug, PartialEq)]
+struct HeightMap {
+    grid: Grid<u8>,
+    adjacency: Adjacency,
+}
+
+impl HeightMap {
+    fn parse(lines: &[String], adjacency: Adjacency) -> Result<Self, String> {
+        Ok(Self {
+            grid: Grid::parse_digits(lines)?,
+            adjacency,
+        })
+    }
+
+    /// Positions lower than all of their neighbours.
+    fn low_points(&self) -> Vec<(usize, usize)> {
+        self.grid
+            .iter()
+            .filter(|(pos, height)| {
+                self.grid
+                    .neighbours(*pos, self.adjacency)
+                    .all(|n| self.grid[n] > **height)
+            })
+            .map(|(pos, _)| pos)
+            .collect()
+    }
+
+    fn risk_level(&self) -> u32 {
+        self.low_points()
+            .iter()
+            .map(|pos| self.grid[*pos] as u32 + 1)
+            .sum()
+    }
+
+    /// Floods the basin around a low point up to the walls of height 9.
+    fn basin_size(&self, low: (usize, usize), visited: &mut Grid<bool>) -> usize {
+        let mut stack = vec![low];
+        let mut size = 0;
+        visited[low] = true;
+        while let Some(pos) = stack.pop() {
+            size += 1;
+            for n in self.grid.neighbours(pos, self.adjacency) {
+                if !visited[n] && self.grid[n] < 9 {
+                    visited[n] = true;
+                    stack.push(n);
+                }
+            }
+        }
+
+        size
+    }
+
+    fn basin_sizes(&self) -> Vec<usize> {
+        let mut visited = Grid::filled(self.grid.width(), self.grid.height(), false);
+        let mut sizes = Vec::new();
+        // With diagonals, several low points can share a basin.
+        for low in self.low_points() {
+            if !visited[low] {
+                sizes.push(self.basin_size(low, &mut visited));
+            }
+        }
+
+        sizes
+    }
+
+    fn largest_basins(&self, count: usize) -> usize {
+        let mut sizes = self.basin_sizes();
+        sizes.sort_unstable_by(|a, b| b.cmp(a));
+        sizes.iter().take(count).product()
+    }
+}
+
+impl Challenge<String> for Day9 {
+    fn new(input_file: &str) -> Self {
+        Self {
+            data: Self::load(input_file).unwrap(),
+            adjacency: Adjacency::Four,
+        }
+    }
+
+    fn run(&self, part: u32) -> Result<String, String> {
+        match part {
+            1 => self.run_part_one(),
+            2 => self.run_part_two(),
+            x => unimplemented!(
+                "Invalid part {} for Day {}",
+                x,
+                std::any::type_name::<Self>()
+            ),
+        }
+    }
+}
+
+impl Day9 {
+    /// Lets low points and basins extend to diagonal neighbours.
+    pub fn with_adjacency(mut self, adjacency: Adjacency) -> Self {
+        self.adjacency = adjacency;
+        self
+    }
+
+    fn run_part_one(&self) -> Result<String, String> {
+        let map = HeightMap::parse(&self.data[..], self.adjacency)?;
+        Ok(format!("{:#?}", map.risk_level()))
+    }
+
+    fn run_part_two(&self) -> Result<String, String> {
+        let map = HeightMap::parse(&self.data[..], self.adjacency)?;
+        Ok(format!("{:#?}", map.largest_basins(3)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_example(adjacency: Adjacency) -> HeightMap {
+        let input: Vec<String> = [
+            "2199943210",
+            "3987894921",
+            "9856789892",
+            "8767896789",
+            "9899965678",
+        ]
+        .iter()
+        .map(|l| l.to_string())
+        .collect();
+        HeightMap::parse(&input, adjacency).unwrap()
+    }
+
+    #[test]
+    fn test_low_points() {
+        let map = get_example(Adjacency::Four);
+        assert_eq!(vec![(1, 0), (9, 0), (2, 2), (6, 4)], map.low_points());
+    }
+
+    #[test]
+    fn test_risk_level() {
+        let map = get_example(Adjacency::Four);
+        assert_eq!(15, map.risk_level());
+    }
+
+    #[test]
+    fn test_basin_sizes() {
+        let map = get_example(Adjacency::Four);
+        assert_eq!(vec![3, 9, 14, 9], map.basin_sizes());
+        assert_eq!(1134, map.largest_basins(3));
+    }
+
+    #[test]
+    fn test_diagonal_adjacency() {
+        // The low points stay, but the basins leak into each other diagonally.
+        let map = get_example(Adjacency::Eight);
+        assert_eq!(vec![(1, 0), (9, 0), (2, 2), (6, 4)], map.low_points());
+        assert_eq!(15, map.risk_level());
+        assert_eq!(vec![35], map.basin_sizes());
+        assert_eq!(35, map.largest_basins(3));
+    }
+}
@@ -38,6 +38,175 @@ fn main() {
                 .default_value("1")
                 .multiple(false),
         )
+        .arg(
+            Arg::with_name("tie-break")
+                .long("tie-break")
+                .help("Day 4: pick the first or last board when several win on the same draw")
+                .possible_values(&["first", "last"])
+                .default_value("first")
+                .multiple(false),
+        )
+        .arg(
+            Arg::with_name("replay")
+                .long("replay")
+                .help("Day 4: replay the game draw by draw before printing the result")
+                .possible_values(&["step", "transcript"])
+                .takes_value(true)
+                .multiple(false),
+        )
+        .arg(
+            Arg::with_name("no-color")
+                .long("no-color")
+                .help("Disable ANSI colors in rendered output")
+                .multiple(false),
+        )
+        .arg(
+            Arg::with_name("segments")
+                .long("segments")
+                .help("Day 5: only count straight, only diagonal or all vent segments")
+                .possible_values(&["straight", "diagonal", "all"])
+                .takes_value(true)
+                .multiple(false),
+        )
+        .arg(
+            Arg::with_name("render")
+                .long("render")
+                .help("Day 5: render the vent map as ASCII or as a PGM heatmap")
+                .possible_values(&["ascii", "pgm"])
+                .takes_value(true)
+                .multiple(false),
+        )
+        .arg(
+            Arg::with_name("render-to")
+                .long("render-to")
+                .help("Write the rendered output to a file instead of stdout")
+                .takes_value(true)
+                .multiple(false),
+        )
+        .arg(
+            Arg::with_name("days")
+                .long("days")
+                .help("Day 6: number of days to simulate")
+                .takes_value(true)
+                .multiple(false),
+        )
+        .arg(
+            Arg::with_name("modulo")
+                .long("modulo")
+                .help("Day 6: report the fish count modulo this number")
+                .takes_value(true)
+                .multiple(false),
+        )
+        .arg(
+            Arg::with_name("spawn-interval")
+                .long("spawn-interval")
+                .help("Day 6: days between two spawns of an adult fish")
+                .default_value("7")
+                .multiple(false),
+        )
+        .arg(
+            Arg::with_name("newborn-delay")
+                .long("newborn-delay")
+                .help("Day 6: extra days a newborn fish needs before its first spawn")
+                .default_value("2")
+                .multiple(false),
+        )
+        .arg(
+            Arg::with_name("death-age")
+                .long("death-age")
+                .help("Day 6: age in days at which a fish dies")
+                .takes_value(true)
+                .multiple(false),
+        )
+        .arg(
+            Arg::with_name("history")
+                .long("history")
+                .help("Day 6: export the population of every day as CSV or JSON")
+                .possible_values(&["csv", "json"])
+                .takes_value(true)
+                .multiple(false),
+        )
+        .arg(
+            Arg::with_name("history-to")
+                .long("history-to")
+                .help("Write the exported history to a file instead of stdout")
+                .takes_value(true)
+                .multiple(false),
+        )
+        .arg(
+            Arg::with_name("fuel")
+                .long("fuel")
+                .help("Day 7: linear, triangular, quadratic or table:<fuel per step,...>")
+                .takes_value(true)
+                .multiple(false),
+        )
+        .arg(
+            Arg::with_name("curve")
+                .long("curve")
+                .help("Day 7: Export the fuel for every position as CSV")
+                .multiple(false),
+        )
+        .arg(
+            Arg::with_name("curve-to")
+                .long("curve-to")
+                .help("Write the exported cost curve to a file instead of stdout")
+                .takes_value(true)
+                .multiple(false),
+        )
+        .arg(
+            Arg::with_name("wirings")
+                .long("wirings")
+                .help("Day 8: Print the wiring found for each line")
+                .multiple(false),
+        )
+        .arg(
+            Arg::with_name("draw")
+                .long("draw")
+                .help("Day 8: Draw the decoded outputs with the wire driving each segment")
+                .multiple(false),
+        )
+        .arg(
+            Arg::with_name("invalid-lines")
+                .long("invalid-lines")
+                .help("Day 8: skip or reject note lines that cannot be read or decoded")
+                .possible_values(&["skip", "reject"])
+                .default_value("reject")
+                .multiple(false),
+        )
+        .arg(
+            Arg::with_name("adjacency")
+                .long("adjacency")
+                .help("Day 9: count 4 or 8 neighbours around each location")
+                .possible_values(&["4", "8"])
+                .default_value("4")
+                .multiple(false),
+        )
+        .arg(
+            Arg::with_name("trace")
+                .long("trace")
+                .help("Day 11: print the energy levels after every step")
+                .multiple(false),
+        )
+        .arg(
+            Arg::with_name("search")
+                .long("search")
+                .help("Day 15: the shortest path algorithm")
+                .possible_values(&["dijkstra", "astar"])
+                .default_value("dijkstra")
+                .multiple(false),
+        )
+        .arg(
+            Arg::with_name("show-path")
+                .long("show-path")
+                .help("Day 15: print the map with the lowest risk path")
+                .multiple(false),
+        )
+        .arg(
+            Arg::with_name("ast")
+                .long("ast")
+                .help("Day 16: print the decoded packet tree")
+                .multiple(false),
+        )
         .arg(
             Arg::with_name("INPUT")
                 .help("Sets the input")
@@ -67,11 +236,139 @@ fn main() {
         1 => day1::Day1::new(&input_file).run(part),
         2 => day2::Day2::new(&input_file).run(part),
         3 => day3::Day3::new(&input_file).run(part),
-        4 => day4::Day4::new(&input_file).run(part),
-        5 => day5::Day5::new(&input_file).run(part),
-        6 => day6::Day6::new(&input_file).run(part),
-        7 => day7::Day7::new(&input_file).run(part),
-        8 => day8::Day8::new(&input_file).run(part),
+        4 => {
+            let tie_break = parse_arg(matches.value_of("tie-break")).unwrap_or_else(|err| {
+                eprintln!("Problem converting tie-break: {:?}", err);
+                process::exit(1);
+            });
+            let replay = matches.value_of("replay").map(|_| {
+                parse_arg(matches.value_of("replay")).unwrap_or_else(|err| {
+                    eprintln!("Problem converting replay: {:?}", err);
+                    process::exit(1);
+                })
+            });
+            day4::Day4::new(&input_file)
+                .with_tie_break(tie_break)
+                .with_replay(replay, !matches.is_present("no-color"))
+                .run(part)
+        }
+        5 => {
+            let filter = matches.value_of("segments").map(|_| {
+                parse_arg(matches.value_of("segments")).unwrap_or_else(|err| {
+                    eprintln!("Problem converting segments: {:?}", err);
+                    process::exit(1);
+                })
+            });
+            let render = matches.value_of("render").map(|_| {
+                parse_arg(matches.value_of("render")).unwrap_or_else(|err| {
+                    eprintln!("Problem converting render: {:?}", err);
+                    process::exit(1);
+                })
+            });
+            day5::Day5::new(&input_file)
+                .with_filter(filter)
+                .with_render(render, matches.value_of("render-to").map(String::from))
+                .run(part)
+        }
+        6 => {
+            let days = matches.value_of("days").map(|_| {
+                parse_arg(matches.value_of("days")).unwrap_or_else(|err| {
+                    eprintln!("Problem converting days: {:?}", err);
+                    process::exit(1);
+                })
+            });
+            let modulo = matches.value_of("modulo").map(|_| {
+                parse_arg(matches.value_of("modulo")).unwrap_or_else(|err| {
+                    eprintln!("Problem converting modulo: {:?}", err);
+                    process::exit(1);
+                })
+            });
+            let history = matches.value_of("history").map(|_| {
+                parse_arg(matches.value_of("history")).unwrap_or_else(|err| {
+                    eprintln!("Problem converting history: {:?}", err);
+                    process::exit(1);
+                })
+            });
+            let config = day6::LifecycleConfig {
+                spawn_interval: parse_arg(matches.value_of("spawn-interval")).unwrap_or_else(
+                    |err| {
+                        eprintln!("Problem converting spawn-interval: {:?}", err);
+                        process::exit(1);
+                    },
+                ),
+                newborn_delay: parse_arg(matches.value_of("newborn-delay")).unwrap_or_else(|err| {
+                    eprintln!("Problem converting newborn-delay: {:?}", err);
+                    process::exit(1);
+                }),
+                death_age: matches.value_of("death-age").map(|_| {
+                    parse_arg(matches.value_of("death-age")).unwrap_or_else(|err| {
+                        eprintln!("Problem converting death-age: {:?}", err);
+                        process::exit(1);
+                    })
+                }),
+            };
+            day6::Day6::new(&input_file)
+                .with_days(days)
+                .with_modulo(modulo)
+                .with_lifecycle(config)
+                .with_history(history, matches.value_of("history-to").map(String::from))
+                .run(part)
+        }
+        7 => {
+            let fuel = matches.value_of("fuel").map(|_| {
+                parse_arg(matches.value_of("fuel")).unwrap_or_else(|err| {
+                    eprintln!("Problem converting fuel: {:?}", err);
+                    process::exit(1);
+                })
+            });
+            day7::Day7::new(&input_file)
+                .with_fuel(fuel)
+                .with_curve(
+                    matches.is_present("curve"),
+                    matches.value_of("curve-to").map(String::from),
+                )
+                .run(part)
+        }
+        8 => {
+            let policy = parse_arg(matches.value_of("invalid-lines")).unwrap_or_else(|err| {
+                eprintln!("Problem converting invalid-lines: {:?}", err);
+                process::exit(1);
+            });
+            day8::Day8::new(&input_file)
+                .with_wirings(matches.is_present("wirings"))
+                .with_draw(matches.is_present("draw"))
+                .with_policy(policy)
+                .run(part)
+        }
+        9 => {
+            let adjacency = parse_arg(matches.value_of("adjacency")).unwrap_or_else(|err| {
+                eprintln!("Problem converting adjacency: {:?}", err);
+                process::exit(1);
+            });
+            day9::Day9::new(&input_file)
+                .with_adjacency(adjacency)
+                .run(part)
+        }
+        10 => day10::Day10::new(&input_file).run(part),
+        11 => day11::Day11::new(&input_file)
+            .with_trace(matches.is_present("trace"))
+            .run(part),
+        12 => day12::Day12::new(&input_file).run(part),
+        13 => day13::Day13::new(&input_file).run(part),
+        14 => day14::Day14::new(&input_file).run(part),
+        15 => {
+            let search = parse_arg(matches.value_of("search")).unwrap_or_else(|err| {
+                eprintln!("Problem converting search: {:?}", err);
+                process::exit(1);
+            });
+            day15::Day15::new(&input_file)
+                .with_search(search)
+                .with_show_path(matches.is_present("show-path"))
+                .run(part)
+        }
+        16 => day16::Day16::new(&input_file)
+            .with_ast(matches.is_present("ast"))
+            .run(part),
         x => unimplemented!("Invalid day: {}", x),
     }
     .unwrap_or_else(|err| {
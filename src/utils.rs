@@ -1 +1,6 @@
+pub mod brackets;
 pub mod challenge;
+pub mod graph;
+pub mod grid;
+pub mod pathfinding;
+pub mod simulation;
@@ -0,0 +1,115 @@
+/// The outcome of matching the brackets of a line.
+#[derive(Debug, PartialEq)]
+pub enum Syntax {
+    Valid,
+    /// The first closing bracket that does not match the open one, if any.
+    Corrupted {
+        position: usize,
+        expected: Option<char>,
+        found: char,
+    },
+    /// The closing brackets that complete the line.
+    Incomplete(String),
+}
+
+/// Matches opening and closing brackets with a stack. The default matcher
+/// knows `()`, `[]`, `{}` and `<>`.
+#[derive(Debug)]
+pub struct BracketMatcher {
+    pairs: Vec<(char, char)>,
+}
+
+impl Default for BracketMatcher {
+    fn default() -> Self {
+        Self::new(&[('(', ')'), ('[', ']'), ('{', '}'), ('<', '>')])
+    }
+}
+
+impl BracketMatcher {
+    /// Builds a matcher from `(open, close)` bracket pairs.
+    pub fn new(pairs: &[(char, char)]) -> Self {
+        Self {
+            pairs: pairs.to_vec(),
+        }
+    }
+
+    fn closing(&self, open: char) -> Option<char> {
+        self.pairs.iter().find(|(o, _)| *o == open).map(|(_, c)| *c)
+    }
+
+    fn is_closing(&self, c: char) -> bool {
+        self.pairs.iter().any(|(_, close)| *close == c)
+    }
+
+    /// Classifies a line, which may only contain brackets of the known pairs.
+    pub fn classify(&self, line: &str) -> Result<Syntax, String> {
+        let mut expected = Vec::new();
+        for (position, c) in line.chars().enumerate() {
+            if let Some(close) = self.closing(c) {
+                expected.push(close);
+            } else if self.is_closing(c) {
+                match expected.pop() {
+                    Some(close) if close == c => {}
+                    close => {
+                        return Ok(Syntax::Corrupted {
+                            position,
+                            expected: close,
+                            found: c,
+                        })
+                    }
+                }
+            } else {
+                return Err(format!("Invalid character {:?} at {}", c, position));
+            }
+        }
+
+        if expected.is_empty() {
+            Ok(Syntax::Valid)
+        } else {
+            Ok(Syntax::Incomplete(expected.iter().rev().collect()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        let matcher = BracketMatcher::default();
+        assert_eq!(Ok(Syntax::Valid), matcher.classify("[<>({}){}[([])<>]]"));
+        assert_eq!(Ok(Syntax::Valid), matcher.classify(""));
+        assert_eq!(
+            Ok(Syntax::Corrupted {
+                position: 12,
+                expected: Some(']'),
+                found: '}'
+            }),
+            matcher.classify("{([(<{}[<>[]}>{[]{[(<()>")
+        );
+        assert_eq!(
+            Ok(Syntax::Corrupted {
+                position: 0,
+                expected: None,
+                found: ')'
+            }),
+            matcher.classify(")")
+        );
+        assert_eq!(
+            Ok(Syntax::Incomplete(String::from("}}]])})]"))),
+            matcher.classify("[({(<(())[]>[[{[]{<()<>>")
+        );
+        assert!(matcher.classify("(a)").is_err());
+    }
+
+    #[test]
+    fn test_custom_pairs() {
+        let matcher = BracketMatcher::new(&[('a', 'b')]);
+        assert_eq!(Ok(Syntax::Valid), matcher.classify("aabb"));
+        assert_eq!(
+            Ok(Syntax::Incomplete(String::from("bb"))),
+            matcher.classify("aa")
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+/// An undirected graph whose string labels are interned to numeric ids.
+#[derive(Debug, Default, PartialEq)]
+pub struct Graph {
+    labels: Vec<String>,
+    ids: HashMap<String, usize>,
+    edges: Vec<Vec<usize>>,
+}
+
+/// Nodes visited so far on a path, as bits of their ids.
+type Visited = u64;
+
+impl Graph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the id of a label, adding a node for it if it is new.
+    pub fn intern(&mut self, label: &str) -> usize {
+        if let Some(id) = self.ids.get(label) {
+            return *id;
+        }
+
+        let id = self.labels.len();
+        self.labels.push(label.to_string());
+        self.ids.insert(label.to_string(), id);
+        self.edges.push(Vec::new());
+        id
+    }
+
+    pub fn add_edge(&mut self, a: &str, b: &str) {
+        let (a, b) = (self.intern(a), self.intern(b));
+        self.edges[a].push(b);
+        self.edges[b].push(a);
+    }
+
+    pub fn id(&self, label: &str) -> Option<usize> {
+        self.ids.get(label).copied()
+    }
+
+    pub fn label(&self, id: usize) -> &str {
+        &self.labels[id]
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn neighbours(&self, id: usize) -> &[usize] {
+        &self.edges[id]
+    }
+
+    /// Counts the paths from `start` to `end`. Nodes for which `once` holds
+    /// may be visited only once, except that up to `revisits` different ones
+    /// other than `start` may be visited a second time. Two adjacent
+    /// unrestricted nodes would allow endless paths and are rejected.
+    pub fn count_paths<F>(
+        &self,
+        start: usize,
+        end: usize,
+        revisits: usize,
+        once: F,
+    ) -> Result<u64, String>
+    where
+        F: Fn(usize) -> bool,
+    {
+        if self.node_count() > Visited::BITS as usize {
+            return Err(format!(
+                "Cannot track more than {} nodes, found {}",
+                Visited::BITS,
+                self.node_count()
+            ));
+        }
+        for node in (0..self.node_count()).filter(|n| !once(*n)) {
+            if let Some(next) = self.neighbours(node).iter().find(|n| !once(**n)) {
+                return Err(format!(
+                    "Endless paths between {} and {}",
+                    self.label(node),
+                    self.label(*next)
+                ));
+            }
+        }
+
+        let mut paths = PathCounter {
+            graph: self,
+            start,
+            end,
+            once: &once,
+            memo: HashMap::new(),
+        };
+        Ok(paths.count(start, 1 << start, 0, revisits))
+    }
+}
+
+/// Depth first search memoised on the node, the visited nodes, the nodes
+/// visited twice and the remaining revisits.
+struct PathCounter<'a, F> {
+    graph: &'a Graph,
+    start: usize,
+    end: usize,
+    once: &'a F,
+    memo: HashMap<(usize, Visited, Visited, usize), u64>,
+}
+
+impl<F: Fn(usize) -> bool> PathCounter<'_, F> {
+    fn count(&mut self, node: usize, visited: Visited, twice: Visited, revisits: usize) -> u64 {
+        if node == self.end {
+            return 1;
+        }
+        let key = (node, visited, twice, revisits);
+        if let Some(paths) = self.memo.get(&key) {
+            return *paths;
+        }
+
+        let mut paths = 0;
+        for &next in self.graph.neighbours(node) {
+            if !(self.once)(next) {
+                paths += self.count(next, visited, twice, revisits);
+            } else if visited & 1 << next == 0 {
+                paths += self.count(next, visited | 1 << next, twice, revisits);
+            } else if revisits > 0 && twice & 1 << next == 0 && next != self.start {
+                paths += self.count(next, visited, twice | 1 << next, revisits - 1);
+            }
+        }
+
+        self.memo.insert(key, paths);
+        paths
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intern() {
+        let mut graph = Graph::new();
+        assert_eq!(0, graph.intern("a"));
+        assert_eq!(1, graph.intern("b"));
+        assert_eq!(0, graph.intern("a"));
+        graph.add_edge("a", "c");
+        assert_eq!(Some(2), graph.id("c"));
+        assert_eq!(None, graph.id("d"));
+        assert_eq!("c", graph.label(2));
+        assert_eq!(3, graph.node_count());
+        assert_eq!(&[2], graph.neighbours(0));
+        assert_eq!(&[0], graph.neighbours(2));
+    }
+
+    #[test]
+    fn test_count_paths() {
+        // A square a-b-d-c-a with a diagonal b-c.
+        let mut graph = Graph::new();
+        for (a, b) in [("a", "b"), ("b", "d"), ("d", "c"), ("c", "a"), ("b", "c")].iter() {
+            graph.add_edge(a, b);
+        }
+        let (a, d) = (graph.id("a").unwrap(), graph.id("d").unwrap());
+        assert_eq!(Ok(4), graph.count_paths(a, d, 0, |_| true));
+        // Adds a-b-c-b-d and a-c-b-c-d, the start may not be revisited.
+        assert_eq!(Ok(6), graph.count_paths(a, d, 1, |_| true));
+
+        graph.add_edge("b", "e");
+        graph.add_edge("e", "f");
+        let e = graph.id("e").unwrap();
+        assert!(graph.count_paths(a, d, 0, |n| n < e).is_err());
+    }
+
+    #[test]
+    fn test_count_paths_revisits() {
+        // Only x can be revisited, through the unrestricted B.
+        let mut graph = Graph::new();
+        for (a, b) in [("a", "B"), ("B", "x"), ("B", "d")].iter() {
+            graph.add_edge(a, b);
+        }
+        let (a, d) = (graph.id("a").unwrap(), graph.id("d").unwrap());
+        let b = graph.id("B").unwrap();
+        assert_eq!(Ok(2), graph.count_paths(a, d, 0, |n| n != b));
+        assert_eq!(Ok(3), graph.count_paths(a, d, 1, |n| n != b));
+        // A second revisit may not enter x a third time.
+        assert_eq!(Ok(3), graph.count_paths(a, d, 2, |n| n != b));
+    }
+}
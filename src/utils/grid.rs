@@ -0,0 +1,216 @@
+use std::ops::{Index, IndexMut};
+use std::str::FromStr;
+
+/// Which cells count as neighbours of a cell.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Adjacency {
+    /// Up, down, left and right.
+    Four,
+    /// Including the diagonals.
+    Eight,
+}
+
+impl FromStr for Adjacency {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "4" => Ok(Self::Four),
+            "8" => Ok(Self::Eight),
+            x => Err(format!("Unknown adjacency: {}", x)),
+        }
+    }
+}
+
+impl Adjacency {
+    fn offsets(&self) -> &'static [(isize, isize)] {
+        match self {
+            Self::Four => &[(0, -1), (-1, 0), (1, 0), (0, 1)],
+            Self::Eight => &[
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+                (-1, 0),
+                (1, 0),
+                (-1, 1),
+                (0, 1),
+                (1, 1),
+            ],
+        }
+    }
+}
+
+/// A rectangular grid stored row by row and indexed by `(x, y)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn filled(width: usize, height: usize, value: T) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![value; width * height],
+        }
+    }
+}
+
+impl Grid<u8> {
+    /// Parses lines of equal length with one digit per cell.
+    pub fn parse_digits(lines: &[String]) -> Result<Self, String> {
+        let width = lines.first().map_or(0, |l| l.len());
+        let mut cells = Vec::with_capacity(width * lines.len());
+        for (y, line) in lines.iter().enumerate() {
+            if line.len() != width {
+                return Err(format!(
+                    "Line {} has {} cells, expected {}",
+                    y + 1,
+                    line.len(),
+                    width
+                ));
+            }
+            for c in line.chars() {
+                let digit =
+                    c.to_digit(10)
+                        .ok_or(format!("Invalid digit {:?} in line {}", c, y + 1))?;
+                cells.push(digit as u8);
+            }
+        }
+
+        Ok(Self {
+            width,
+            height: lines.len(),
+            cells,
+        })
+    }
+}
+
+impl<T> Grid<T> {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Whether a position, which may lie outside, is on the grid.
+    pub fn contains(&self, (x, y): (isize, isize)) -> bool {
+        x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height
+    }
+
+    /// Iterates over all cells row by row with their positions.
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, cell)| ((i % width, i / width), cell))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = ((usize, usize), &mut T)> {
+        let width = self.width;
+        self.cells
+            .iter_mut()
+            .enumerate()
+            .map(move |(i, cell)| ((i % width, i / width), cell))
+    }
+
+    /// Iterates over the neighbours of a position that are on the grid.
+    pub fn neighbours(
+        &self,
+        (x, y): (usize, usize),
+        adjacency: Adjacency,
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        adjacency
+            .offsets()
+            .iter()
+            .map(move |(dx, dy)| (x as isize + dx, y as isize + dy))
+            .filter(move |pos| self.contains(*pos))
+            .map(|(x, y)| (x as usize, y as usize))
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        assert!(x < self.width, "Column {} out of bounds", x);
+        &self.cells[y * self.width + x]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        assert!(x < self.width, "Column {} out of bounds", x);
+        &mut self.cells[y * self.width + x]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(input: &[&str]) -> Vec<String> {
+        input.iter().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_digits() {
+        let grid = Grid::parse_digits(&lines(&["123", "456"])).unwrap();
+        assert_eq!(3, grid.width());
+        assert_eq!(2, grid.height());
+        assert_eq!(1, grid[(0, 0)]);
+        assert_eq!(6, grid[(2, 1)]);
+        let cells: Vec<u8> = grid.iter().map(|(_, c)| *c).collect();
+        assert_eq!(vec![1, 2, 3, 4, 5, 6], cells);
+        assert_eq!(((1, 1), &5), grid.iter().nth(4).unwrap());
+
+        assert_eq!(
+            Err(String::from("Line 2 has 2 cells, expected 3")),
+            Grid::parse_digits(&lines(&["123", "45"]))
+        );
+        assert_eq!(
+            Err(String::from("Invalid digit 'x' in line 1")),
+            Grid::parse_digits(&lines(&["1x3"]))
+        );
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = Grid::filled(3, 3, 0);
+        assert!(grid.contains((2, 2)));
+        assert!(!grid.contains((3, 0)));
+        assert!(!grid.contains((0, -1)));
+
+        let four: Vec<_> = grid.neighbours((0, 0), Adjacency::Four).collect();
+        assert_eq!(vec![(1, 0), (0, 1)], four);
+        assert_eq!(4, grid.neighbours((1, 1), Adjacency::Four).count());
+        assert_eq!(8, grid.neighbours((1, 1), Adjacency::Eight).count());
+        let eight: Vec<_> = grid.neighbours((2, 2), Adjacency::Eight).collect();
+        assert_eq!(vec![(1, 1), (2, 1), (1, 2)], eight);
+    }
+
+    #[test]
+    fn test_index_mut() {
+        let mut grid = Grid::filled(2, 2, false);
+        grid[(1, 0)] = true;
+        assert!(grid[(1, 0)]);
+        assert_eq!(1, grid.iter().filter(|(_, c)| **c).count());
+
+        for ((x, y), cell) in grid.iter_mut() {
+            *cell = x == y;
+        }
+        assert!(grid[(0, 0)] && grid[(1, 1)] && !grid[(1, 0)]);
+    }
+
+    #[test]
+    fn test_adjacency_from_str() {
+        assert_eq!(Ok(Adjacency::Four), Adjacency::from_str("4"));
+        assert_eq!(Ok(Adjacency::Eight), Adjacency::from_str("8"));
+        assert!(Adjacency::from_str("6").is_err());
+    }
+}
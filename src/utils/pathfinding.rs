@@ -0,0 +1,132 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::Hash;
+
+/// The cheapest path found from the start to a goal, both included.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path<N> {
+    pub cost: u64,
+    pub nodes: Vec<N>,
+}
+
+/// Finds the cheapest path with Dijkstra's algorithm. `successors` yields the
+/// neighbours of a node with the cost of moving there.
+pub fn dijkstra<N, S, I, G>(start: N, successors: S, is_goal: G) -> Option<Path<N>>
+where
+    N: Eq + Hash + Clone,
+    S: FnMut(&N) -> I,
+    I: IntoIterator<Item = (N, u64)>,
+    G: Fn(&N) -> bool,
+{
+    astar(start, successors, |_| 0, is_goal)
+}
+
+/// Finds the cheapest path with A*, guided by a heuristic that must never
+/// overestimate the remaining cost and must not drop by more than the cost
+/// of any move.
+pub fn astar<N, S, I, H, G>(
+    start: N,
+    mut successors: S,
+    heuristic: H,
+    is_goal: G,
+) -> Option<Path<N>>
+where
+    N: Eq + Hash + Clone,
+    S: FnMut(&N) -> I,
+    I: IntoIterator<Item = (N, u64)>,
+    H: Fn(&N) -> u64,
+    G: Fn(&N) -> bool,
+{
+    // Nodes are numbered in the order they are discovered, so the queue only
+    // needs to order numbers.
+    let mut nodes = vec![start.clone()];
+    let mut ids = HashMap::new();
+    ids.insert(start.clone(), 0);
+    let mut cost = vec![0];
+    let mut parent: Vec<Option<usize>> = vec![None];
+    let mut queue = BinaryHeap::new();
+    queue.push(Reverse((heuristic(&start), 0, 0)));
+
+    while let Some(Reverse((_, g, id))) = queue.pop() {
+        if g > cost[id] {
+            continue;
+        }
+        if is_goal(&nodes[id]) {
+            return Some(Path {
+                cost: g,
+                nodes: reconstruct(&nodes, &parent, id),
+            });
+        }
+
+        for (next, step) in successors(&nodes[id]) {
+            let next_cost = g + step;
+            let next_id = match ids.get(&next) {
+                Some(&next_id) if next_cost >= cost[next_id] => continue,
+                Some(&next_id) => next_id,
+                None => {
+                    nodes.push(next.clone());
+                    ids.insert(next.clone(), nodes.len() - 1);
+                    cost.push(next_cost);
+                    parent.push(None);
+                    nodes.len() - 1
+                }
+            };
+            cost[next_id] = next_cost;
+            parent[next_id] = Some(id);
+            queue.push(Reverse((next_cost + heuristic(&next), next_cost, next_id)));
+        }
+    }
+
+    None
+}
+
+/// Follows the parents back from a node to the start.
+fn reconstruct<N: Clone>(nodes: &[N], parent: &[Option<usize>], mut id: usize) -> Vec<N> {
+    let mut path = vec![nodes[id].clone()];
+    while let Some(previous) = parent[id] {
+        path.push(nodes[previous].clone());
+        id = previous;
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A small road network where the direct road is the most expensive.
+    fn roads(node: &char) -> Vec<(char, u64)> {
+        match node {
+            'a' => vec![('b', 1), ('c', 4), ('d', 10)],
+            'b' => vec![('c', 2), ('d', 8)],
+            'c' => vec![('d', 3)],
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn test_dijkstra() {
+        let path = dijkstra('a', roads, |n| *n == 'd').unwrap();
+        assert_eq!(6, path.cost);
+        assert_eq!(vec!['a', 'b', 'c', 'd'], path.nodes);
+
+        let path = dijkstra('a', roads, |n| *n == 'a').unwrap();
+        assert_eq!(0, path.cost);
+        assert_eq!(vec!['a'], path.nodes);
+
+        assert_eq!(None, dijkstra('a', roads, |n| *n == 'e'));
+    }
+
+    #[test]
+    fn test_astar() {
+        // Walk a line of numbers towards 10, each step costs 1.
+        let successors = |n: &i64| vec![(n - 1, 1), (n + 1, 1)];
+        let path = astar(0, successors, |n| (10 - n).unsigned_abs(), |n| *n == 10).unwrap();
+        assert_eq!(10, path.cost);
+        assert_eq!((0..=10).collect::<Vec<i64>>(), path.nodes);
+
+        let path = astar('a', roads, |_| 0, |n| *n == 'd').unwrap();
+        assert_eq!(Some(path), dijkstra('a', roads, |n| *n == 'd'));
+    }
+}
@@ -0,0 +1,38 @@
+use std::fmt::Display;
+
+/// A system that advances in discrete steps and can show its state.
+pub trait Simulation: Display {
+    /// What a single step reports.
+    type Outcome;
+
+    fn step(&mut self) -> Self::Outcome;
+
+    /// Runs a number of steps and returns their outcomes. Tracing prints the
+    /// state after every step.
+    fn run(&mut self, steps: usize, trace: bool) -> Vec<Self::Outcome> {
+        (1..=steps)
+            .map(|n| {
+                let outcome = self.step();
+                if trace {
+                    println!("After step {}:\n{}\n", n, self);
+                }
+                outcome
+            })
+            .collect()
+    }
+
+    /// Returns the first step, counting from one, whose outcome satisfies the
+    /// condition, or `None` if none does within the limit.
+    fn run_until<F>(&mut self, limit: usize, trace: bool, mut done: F) -> Option<usize>
+    where
+        F: FnMut(&Self::Outcome) -> bool,
+    {
+        (1..=limit).find(|n| {
+            let outcome = self.step();
+            if trace {
+                println!("After step {}:\n{}\n", n, self);
+            }
+            done(&outcome)
+        })
+    }
+}